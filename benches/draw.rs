@@ -0,0 +1,28 @@
+use criterion::{Criterion, black_box, criterion_group, criterion_main};
+use imt::{DrawTarget, ScreenBuffer};
+
+const W: usize = 200;
+const H: usize = 60;
+
+fn write_str_row(c: &mut Criterion) {
+    let mut buf = ScreenBuffer::new(W, H);
+    let line = "0123456789".repeat(W / 10);
+    c.bench_function("write_str row", |b| {
+        b.iter(|| buf.write_str(0, black_box(H / 2), black_box(&line)))
+    });
+}
+
+// `flush` prints what `to_string` builds, without the terminal I/O
+fn full_frame(c: &mut Criterion) {
+    let mut buf = ScreenBuffer::new(W, H);
+    let line = "abc def ".repeat(W / 8);
+    for y in 0..H {
+        buf.write_str(0, y, &line);
+    }
+    c.bench_function("full frame to_string", |b| {
+        b.iter(|| black_box(&buf).to_string())
+    });
+}
+
+criterion_group!(benches, write_str_row, full_frame);
+criterion_main!(benches);
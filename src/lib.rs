@@ -1,80 +1,162 @@
-use std::io::{self, Write};
+use std::collections::{HashMap, VecDeque};
+use std::fmt;
+use std::hash::{DefaultHasher, Hash, Hasher};
+use std::io::{self, IsTerminal, Read, Write};
+use std::thread;
+use std::time::{Duration, Instant};
 
-#[derive(Clone, Copy)]
-struct Cell {
+#[cfg(any(test, feature = "testing"))]
+pub mod testing;
+pub mod text;
+
+#[derive(Clone, Copy, Hash, PartialEq, Eq, Debug)]
+pub struct Cell {
     ch: char,
+    fg: Option<Color>,
+    bg: Option<Color>,
+    // index into the buffer's link targets
+    link: Option<usize>,
+}
+impl Cell {
+    // a cell in the terminal's default colors
+    fn plain(ch: char) -> Self {
+        Cell {
+            ch,
+            fg: None,
+            bg: None,
+            link: None,
+        }
+    }
+    pub fn ch(&self) -> char {
+        self.ch
+    }
+    /// Foreground color, `None` for the terminal's default.
+    pub fn fg(&self) -> Option<Color> {
+        self.fg
+    }
+    /// Background color, `None` for the terminal's default.
+    pub fn bg(&self) -> Option<Color> {
+        self.bg
+    }
+}
+/// The 16 standard ANSI colors.
+#[derive(Copy, Clone, PartialEq, Eq, Hash, Debug)]
+pub enum Color {
+    Black,
+    Red,
+    Green,
+    Yellow,
+    Blue,
+    Magenta,
+    Cyan,
+    White,
+    BrightBlack,
+    BrightRed,
+    BrightGreen,
+    BrightYellow,
+    BrightBlue,
+    BrightMagenta,
+    BrightCyan,
+    BrightWhite,
+}
+impl Color {
+    // SGR parameter setting this as the foreground: 30-37, bright 90-97
+    fn fg_code(self) -> u8 {
+        let i = self as u8;
+        if i < 8 { 30 + i } else { 90 + i - 8 }
+    }
+    // as the background: 40-47, bright 100-107
+    fn bg_code(self) -> u8 {
+        self.fg_code() + 10
+    }
 }
 
+// fills the cells covered by the right half of a wide char; skipped on output
+const CONTINUATION: char = '\0';
+/// Largest frame `ScreenBuffer::read_frame` accepts, in cells.
+pub const MAX_FRAME_CELLS: usize = 1 << 24;
+/// Marks a cell of an overlay layer that lets the destination show through.
+/// Printed as a blank.
+pub const TRANSPARENT: char = '\u{FFFF}';
 impl Default for Cell {
     fn default() -> Self {
-        Cell { ch: ' ' }
+        Cell::plain(' ')
     }
 }
+// only clear, put_char, flush and dimensions are required; the rest is built on put_char
 pub trait DrawTarget {
     fn clear(&mut self);
     fn put_char(&mut self, x: usize, y: usize, ch: char);
-    fn write_str(&mut self, x: usize, y: usize, text: &str);
-    fn write_i64_right(&mut self, x: usize, y: usize, value: i64, width: usize);
-    fn write_f64_right(&mut self, x: usize, y: usize, value: f64, width: usize, precision: usize);
-    fn flush(&self);
-    fn draw_hline(&mut self, x: usize, y: usize, w: usize, ch: char);
-    fn draw_vline(&mut self, x: usize, y: usize, h: usize, ch: char);
-    fn draw_frame(&mut self, x: usize, y: usize, w: usize, h: usize);
-}
-pub struct ScreenBuffer {
-    width: usize,
-    height: usize,
-    cells: Vec<Cell>,
-}
-impl ScreenBuffer {
-    pub fn new(width: usize, height: usize) -> Self {
-        Self {
-            width,
-            height,
-            cells: vec![Cell::default(); width * height],
-        }
-    }
-    fn index(&self, x: usize, y: usize) -> usize {
-        y * self.width + x
+    /// `put_char` in a foreground color; targets without colors draw the char alone.
+    fn put_char_fg(&mut self, x: usize, y: usize, ch: char, color: Color) {
+        let _ = color;
+        self.put_char(x, y, ch);
     }
-}
-impl DrawTarget for ScreenBuffer {
-    fn clear(&mut self) {
-        for cell in &mut self.cells {
-            *cell = Cell::default();
-        }
+    /// `put_char` on a background color; targets without colors draw the char alone.
+    fn put_char_bg(&mut self, x: usize, y: usize, ch: char, color: Color) {
+        let _ = color;
+        self.put_char(x, y, ch);
     }
-    fn put_char(&mut self, x: usize, y: usize, ch: char) {
-        if x >= self.width || y >= self.height {
-            return;
-        }
-        let idx = self.index(x, y);
-        self.cells[idx].ch = ch;
+    /// `put_char` with optional foreground and background colors.
+    fn put_char_styled(
+        &mut self,
+        x: usize,
+        y: usize,
+        ch: char,
+        fg: Option<Color>,
+        bg: Option<Color>,
+    ) {
+        let _ = (fg, bg);
+        self.put_char(x, y, ch);
     }
     fn write_str(&mut self, x: usize, y: usize, text: &str) {
-        if y >= self.height {
-            return;
-        }
-        for (i, ch) in text.chars().enumerate() {
-            let px = x + i;
-            if px >= self.width {
-                return;
+        let mut px = x;
+        for ch in text.chars() {
+            let w = self.char_width(ch);
+            if w == 0 {
+                continue;
             }
             self.put_char(px, y, ch);
+            for cx in px.saturating_add(1)..px.saturating_add(w) {
+                self.put_char(cx, y, CONTINUATION);
+            }
+            px = px.saturating_add(w);
         }
     }
+    /// `write_str` for untrusted text: a `\t` is drawn as a space, every other control
+    /// char (the ESC starting an escape sequence, or a `\n` the single row can't show)
+    /// as `␦`. Returns how many were replaced by `␦`.
+    fn write_str_safe(&mut self, x: usize, y: usize, text: &str) -> usize {
+        let mut replaced = 0;
+        let clean: String = text
+            .chars()
+            .map(|ch| match ch {
+                '\t' => ' ',
+                ch if ch.is_control() => {
+                    replaced += 1;
+                    '␦'
+                }
+                ch => ch,
+            })
+            .collect();
+        self.write_str(x, y, &clean);
+        replaced
+    }
     fn write_i64_right(&mut self, x: usize, y: usize, mut value: i64, width: usize) {
-        if y >= self.height {
+        let (bound_x, bound_y) = self.dimensions();
+        if y >= bound_y {
             return;
         }
 
-        for i in 0..width {
-            self.put_char(x + i, y, ' ');
+        // the field may run past the edge; only its visible part is blanked
+        let end = x.saturating_add(width);
+        for px in x..end.min(bound_x) {
+            self.put_char(px, y, ' ');
         }
 
         if value == 0 {
             if width > 0 {
-                self.put_char(x + width - 1, y, '0');
+                self.put_char(end - 1, y, '0');
             }
             return;
         }
@@ -83,7 +165,7 @@ impl DrawTarget for ScreenBuffer {
             value = -value;
         }
 
-        let mut pos = x + width;
+        let mut pos = end;
 
         while value > 0 && pos > x {
             pos -= 1;
@@ -97,7 +179,7 @@ impl DrawTarget for ScreenBuffer {
         }
     }
     fn write_f64_right(&mut self, x: usize, y: usize, value: f64, width: usize, precision: usize) {
-        if y >= self.height {
+        if y >= self.dimensions().1 {
             return;
         }
 
@@ -107,11 +189,12 @@ impl DrawTarget for ScreenBuffer {
         let int_part = scaled / scale;
         let mut fract_part = (scaled % scale).abs();
 
-        for i in 0..width {
-            self.put_char(x + i, y, ' ');
+        let end = x.saturating_add(width);
+        for px in x..end.min(self.dimensions().0) {
+            self.put_char(px, y, ' ');
         }
 
-        let mut pos = x + width;
+        let mut pos = end;
 
         for _ in 0..precision {
             if pos <= x {
@@ -143,207 +226,1105 @@ impl DrawTarget for ScreenBuffer {
             self.put_char(pos - 1, y, '-');
         }
     }
-    fn flush(&self) {
-        let mut out = String::with_capacity(self.width * self.height + self.height);
-
-        out.push_str("\x1B[2J\x1B[H");
-
-        for y in 0..self.height {
-            for x in 0..self.width {
-                out.push(self.cells[self.index(x, y)].ch);
-            }
-            out.push('\n');
-        }
-        print!("{}", out);
-        io::stdout().flush().unwrap();
-    }
+    fn flush(&self);
     fn draw_hline(&mut self, x: usize, y: usize, w: usize, ch: char) {
-        for i in 0..w {
-            if x + 1 >= self.width {
-                return;
-            }
-            self.put_char(x + i, y, ch);
+        let (width, height) = self.dimensions();
+        let line = Rect::new(x, y, w, 1).clamp_to(Rect::new(0, 0, width, height));
+        for px in line.x..line.right() {
+            self.put_char(px, line.y, ch);
         }
     }
     fn draw_vline(&mut self, x: usize, y: usize, h: usize, ch: char) {
-        for i in 0..h {
-            if y + 1 >= self.width {
-                return;
-            }
-            self.put_char(x, y + i, ch);
+        let (width, height) = self.dimensions();
+        let line = Rect::new(x, y, 1, h).clamp_to(Rect::new(0, 0, width, height));
+        for py in line.y..line.bottom() {
+            self.put_char(line.x, py, ch);
         }
     }
+    /// A `w` wide line of `mid` starting with `left_cap` and ending with `right_cap`.
+    fn draw_hline_capped(
+        &mut self,
+        x: usize,
+        y: usize,
+        w: usize,
+        left_cap: char,
+        right_cap: char,
+        mid: char,
+    ) {
+        if w == 0 {
+            return;
+        }
+        self.draw_hline(x, y, w, mid);
+        self.put_char(x.saturating_add(w - 1), y, right_cap);
+        self.put_char(x, y, left_cap);
+    }
     fn draw_frame(&mut self, x: usize, y: usize, w: usize, h: usize) {
+        if w == 0 || h == 0 {
+            return;
+        }
+        // a 1-wide or 1-tall frame collapses onto its corners
+        let (right, bottom) = (x.saturating_add(w - 1), y.saturating_add(h - 1));
         self.put_char(x, y, '┌');
-        self.put_char(x + w - 1, y, '┐');
-        self.put_char(x, y + h - 1, '└');
-        self.put_char(x + w - 1, y + h - 1, '┘');
+        self.put_char(right, y, '┐');
+        self.put_char(x, bottom, '└');
+        self.put_char(right, bottom, '┘');
 
-        self.draw_hline(x + 1, y, w - 2, '-');
-        self.draw_hline(x + 1, y + h - 1, w - 2, '-');
-        self.draw_vline(x, y + 1, h - 2, '|');
-        self.draw_vline(x + w - 1, y + 1, h - 2, '|');
+        self.draw_hline(x + 1, y, w.saturating_sub(2), '-');
+        self.draw_hline(x + 1, bottom, w.saturating_sub(2), '-');
+        self.draw_vline(x, y + 1, h.saturating_sub(2), '|');
+        self.draw_vline(right, y + 1, h.saturating_sub(2), '|');
     }
+    fn dimensions(&self) -> (usize, usize);
+    /// Columns `ch` advances `write_str` by.
+    fn char_width(&self, ch: char) -> usize {
+        text::char_width(ch)
+    }
+    /// Called before a group of draws that belong together, so a backend can coalesce
+    /// its writes until the matching `end_batch`. A `Ui` opens one batch per frame, when
+    /// it's created or cleared, and closes it in `Ui::flush`; batches don't nest.
+    fn begin_batch(&mut self) {}
+    fn end_batch(&mut self) {}
 }
-#[derive(Copy, Clone)]
-pub enum BorderKind {
-    Full,
-    No,
-}
-enum LayoutKind {
-    Vertical,
-    Horizontal,
-}
-pub struct UiGrid<'a, 'b, T>
-where
-    T: DrawTarget,
-{
-    parent: &'b mut Ui<'a, T>,
-    start_x: usize,
-    start_y: usize,
-    cols: usize,
-    spacing: usize,
-    spacing_inner: usize,
-    cell_idx: usize,
-    max_col_width: Vec<usize>,
-    max_row_height: Vec<usize>,
-    draw: bool,
+pub struct ScreenBuffer {
+    width: usize,
+    height: usize,
+    cells: Vec<Cell>,
+    blank: char,
+    trailing_newline: bool,
+    widths: HashMap<char, usize>,
+    last_flush: Option<Instant>,
+    // a throttled flush was skipped since the last one that went through
+    pending: bool,
+    tty_mode: TtyMode,
+    links: Vec<String>,
+    hyperlinks: bool,
 }
-impl<'a, 'b, T> UiGrid<'a, 'b, T>
-where
-    T: DrawTarget,
-{
-    pub fn cell(&mut self, f: impl Fn(&mut Ui<T>)) {
-        let col = self.cell_idx % self.cols;
-        let row = self.cell_idx / self.cols;
-
-        if self.max_col_width.len() < self.cols {
-            self.max_col_width.resize(self.cols, 0);
+impl ScreenBuffer {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            cells: vec![Cell::default(); width * height],
+            blank: ' ',
+            trailing_newline: true,
+            widths: HashMap::new(),
+            last_flush: None,
+            pending: false,
+            tty_mode: TtyMode::Auto,
+            links: Vec::new(),
+            hyperlinks: false,
         }
-
-        if self.max_row_height.len() <= row {
-            self.max_row_height.resize(row + 1, 0);
+    }
+    fn index(&self, x: usize, y: usize) -> usize {
+        y * self.width + x
+    }
+    fn put_cell(&mut self, x: usize, y: usize, cell: Cell) {
+        if x >= self.width || y >= self.height {
+            return;
         }
-
-        let start_x = self.start_x
-            + self.max_col_width[..col].iter().sum::<usize>()
-            + col * self.spacing_inner;
-        let start_y = self.start_y
-            + self.max_row_height[..row].iter().sum::<usize>()
-            + row * self.spacing_inner;
-
-        let mut cell_ui = Ui {
-            buf: self.parent.buf,
-            cursor_x: start_x,
-            cursor_y: start_y,
-            max_x: start_x,
-            max_y: start_y,
-            available_x: Some(self.max_col_width[col]),
-            available_y: Some(self.max_row_height[row]),
-            used_x: 0,
-            used_y: 0,
-            layout: LayoutKind::Horizontal,
-            spacing: self.spacing,
-            draw: self.draw,
-        };
-        f(&mut cell_ui);
-        let used_w = cell_ui.max_x - start_x;
-        self.max_col_width[col] = self.max_col_width[col].max(used_w);
-
-        let used_h = cell_ui.max_y - start_y;
-        self.max_row_height[row] = self.max_row_height[row].max(used_h);
-
-        self.cell_idx += 1;
+        let idx = self.index(x, y);
+        self.cells[idx] = cell;
     }
-}
-pub struct Label<'a> {
-    text: &'a str,
-    width: Option<usize>,
-    align_inner: Align,
-    align_outer: Align,
-}
-impl<'a> From<&'a String> for Label<'a> {
-    fn from(value: &'a String) -> Self {
-        Self {
-            text: value,
-            width: None,
-            align_inner: Align::Left,
-            align_outer: Align::Left,
+    /// A buffer whose cells are all `TRANSPARENT`, to draw an overlay layer into.
+    pub fn new_transparent(width: usize, height: usize) -> Self {
+        let mut buf = Self::new(width, height);
+        buf.cells.fill(Cell::plain(TRANSPARENT));
+        buf
+    }
+    /// Copies `layer` onto this buffer with its top-left at (x, y), skipping
+    /// `TRANSPARENT` cells and anything that falls outside.
+    pub fn overlay(&mut self, layer: &ScreenBuffer, x: usize, y: usize) {
+        for ly in 0..layer.height {
+            for lx in 0..layer.width {
+                let mut cell = layer.cells[layer.index(lx, ly)];
+                cell.link = cell.link.map(|link| self.link_index(&layer.links[link]));
+                if cell.ch != TRANSPARENT {
+                    self.put_cell(x.saturating_add(lx), y.saturating_add(ly), cell);
+                }
+            }
         }
     }
-}
-impl<'a> From<&'a str> for Label<'a> {
-    fn from(value: &'a str) -> Self {
-        Self {
-            text: value,
-            width: None,
-            align_inner: Align::Left,
-            align_outer: Align::Left,
+    /// Writes `text` like `write_str` and links its cells to `url`. With hyperlinks
+    /// enabled, `flush` wraps them in OSC 8 escapes so terminals make them clickable.
+    pub fn put_link(&mut self, x: usize, y: usize, text: &str, url: &str) {
+        self.write_str(x, y, text);
+        if y >= self.height {
+            return;
+        }
+        let link = self.link_index(url);
+        let w: usize = text.chars().map(|ch| self.char_width(ch)).sum();
+        for px in x..x.saturating_add(w).min(self.width) {
+            let idx = self.index(px, y);
+            self.cells[idx].link = Some(link);
         }
     }
-}
-impl<'a> Label<'a> {
-    pub fn with_width(mut self, width: usize) -> Self {
-        self.width = if width > 0 { Some(width) } else { None };
-        self
+    // control chars are dropped from `url` so it can't end the OSC sequence early
+    fn link_index(&mut self, url: &str) -> usize {
+        let url: String = url.chars().filter(|c| !c.is_control()).collect();
+        match self.links.iter().position(|known| *known == url) {
+            Some(i) => i,
+            None => {
+                self.links.push(url);
+                self.links.len() - 1
+            }
+        }
     }
-    pub fn align_inner(mut self, align_inner: Align) -> Self {
-        self.align_inner = align_inner;
-        self
+    /// Whether `flush` emits OSC 8 hyperlinks for `put_link` text, off by default since
+    /// not every terminal ignores the escapes it doesn't know.
+    pub fn set_hyperlinks(&mut self, on: bool) {
+        self.hyperlinks = on;
     }
-    pub fn align_outer(mut self, align_outer: Align) -> Self {
-        self.align_outer = align_outer;
-        self
+    /// Hash of size and cells, for skipping a flush when a rebuilt frame is unchanged.
+    pub fn content_hash(&self) -> u64 {
+        let mut hasher = DefaultHasher::new();
+        (self.width, self.height).hash(&mut hasher);
+        self.cells.hash(&mut hasher);
+        hasher.finish()
     }
-}
-impl<'a> UiElement for Label<'a> {
-    fn render<T: DrawTarget>(&self, ui: &mut Ui<T>) {
-        let text = self.text;
-        let width = self.width;
-        let align_inner = &self.align_inner;
-        let align_outer = &self.align_outer;
-
-        let len = text.len();
-        let w = width.unwrap_or(len);
-        let visible_len = len.min(w);
-
-        let slice = if len > w { &text[..w] } else { text };
-        // outer
-        let start_x = if let Some(avail_x) = ui.available_x {
-            match align_outer {
-                Align::Left => ui.cursor_x,
-                Align::Right => ui.cursor_x + avail_x.saturating_sub(w),
+    /// Glyph printed for blank cells by `flush` and `to_string`; the cells keep their space.
+    pub fn set_blank_glyph(&mut self, ch: char) {
+        self.blank = ch;
+    }
+    /// Overrides the width of `ch`, e.g. for ambiguous-width glyphs a terminal draws wide.
+    pub fn set_char_width(&mut self, ch: char, width: usize) {
+        self.widths.insert(ch, width);
+    }
+    /// Like `to_string`, but with `sep` after each row instead of `'\n'`.
+    pub fn to_string_with_sep(&self, sep: &str) -> String {
+        self.render(sep, false)
+    }
+    // with `colors`, SGR codes go where the colors change and runs reset by the row's end,
+    // so uncolored cells cost no escapes
+    fn render(&self, sep: &str, colors: bool) -> String {
+        let mut out = String::with_capacity((self.width + sep.len()) * self.height);
+        for y in 0..self.height {
+            let row = self.index(0, y);
+            let (mut fg, mut bg) = (None, None);
+            let mut link = None;
+            for cell in &self.cells[row..row + self.width] {
+                if cell.ch == CONTINUATION {
+                    continue;
+                }
+                if colors && (cell.fg, cell.bg) != (fg, bg) {
+                    // there's no code for "default color" in one channel, so dropping
+                    // either color resets both
+                    if (fg.is_some() && cell.fg.is_none()) || (bg.is_some() && cell.bg.is_none()) {
+                        out.push_str("\x1B[0m");
+                        (fg, bg) = (None, None);
+                    }
+                    if let Some(color) = cell.fg.filter(|_| cell.fg != fg) {
+                        out.push_str(&format!("\x1B[{}m", color.fg_code()));
+                    }
+                    if let Some(color) = cell.bg.filter(|_| cell.bg != bg) {
+                        out.push_str(&format!("\x1B[{}m", color.bg_code()));
+                    }
+                    (fg, bg) = (cell.fg, cell.bg);
+                }
+                if colors && self.hyperlinks && cell.link != link {
+                    if link.is_some() {
+                        out.push_str("\x1B]8;;\x07");
+                    }
+                    if let Some(i) = cell.link {
+                        out.push_str(&format!("\x1B]8;;{}\x07", self.links[i]));
+                    }
+                    link = cell.link;
+                }
+                match cell.ch {
+                    ' ' | TRANSPARENT => out.push(self.blank),
+                    ch => out.push(ch),
+                }
             }
-        } else {
-            // no right border known, that we can align to
-            ui.cursor_x
+            if fg.is_some() || bg.is_some() {
+                out.push_str("\x1B[0m");
+            }
+            if link.is_some() {
+                out.push_str("\x1B]8;;\x07");
+            }
+            if y + 1 < self.height || self.trailing_newline {
+                out.push_str(sep);
+            }
+        }
+        out
+    }
+    /// Flushes unless the last throttled flush was less than `min_interval` ago, returning
+    /// whether it did. A skipped frame stays pending until the next flush that goes
+    /// through; call `flush_pending` once the updates stop so the latest state is shown.
+    pub fn flush_throttled(&mut self, min_interval: Duration) -> bool {
+        self.flush_throttled_at(Instant::now(), min_interval)
+    }
+    fn flush_throttled_at(&mut self, now: Instant, min_interval: Duration) -> bool {
+        if self
+            .last_flush
+            .is_some_and(|last| now.saturating_duration_since(last) < min_interval)
+        {
+            self.pending = true;
+            return false;
+        }
+        self.flush_now(now);
+        true
+    }
+    /// Flushes a frame `flush_throttled` skipped, if there is one, returning whether it did.
+    pub fn flush_pending(&mut self) -> bool {
+        if !self.pending {
+            return false;
+        }
+        self.flush_now(Instant::now());
+        true
+    }
+    fn flush_now(&mut self, now: Instant) {
+        self.last_flush = Some(now);
+        self.pending = false;
+        self.flush();
+    }
+    pub fn set_tty_mode(&mut self, mode: TtyMode) {
+        self.tty_mode = mode;
+    }
+    fn ansi(&self) -> bool {
+        match self.tty_mode {
+            TtyMode::Auto => io::stdout().is_terminal(),
+            TtyMode::Ansi => true,
+            TtyMode::Plain => false,
+        }
+    }
+    // the whole frame goes out in one write, so a slow terminal never shows half of it
+    fn write_screen<W: Write>(&self, mut out: W, ansi: bool) -> io::Result<()> {
+        let mut frame = String::new();
+        if ansi {
+            frame.push_str("\x1B[2J\x1B[H");
+        }
+        frame.push_str(&self.render("\n", ansi));
+        out.write_all(frame.as_bytes())?;
+        out.flush()
+    }
+    /// Whether `flush` and `to_string` end the last row with a separator, on by default.
+    pub fn set_trailing_newline(&mut self, on: bool) {
+        self.trailing_newline = on;
+    }
+    pub fn rows(&self) -> impl Iterator<Item = String> + '_ {
+        (0..self.height).map(|y| {
+            (0..self.width)
+                .map(|x| self.cells[self.index(x, y)].ch)
+                .filter(|&ch| ch != CONTINUATION)
+                .collect()
+        })
+    }
+    pub fn bounds(&self) -> Rect {
+        Rect::new(0, 0, self.width, self.height)
+    }
+    /// Smallest rect holding every non-space cell, `None` when all cells are blank.
+    pub fn content_bounds(&self) -> Option<Rect> {
+        let mut bounds: Option<(usize, usize, usize, usize)> = None;
+        for y in 0..self.height {
+            for x in 0..self.width {
+                if self.cells[self.index(x, y)].ch == ' ' {
+                    continue;
+                }
+                bounds = Some(match bounds {
+                    Some((x0, y0, x1, y1)) => (x0.min(x), y0.min(y), x1.max(x), y1.max(y)),
+                    None => (x, y, x, y),
+                });
+            }
+        }
+        bounds.map(|(x0, y0, x1, y1)| Rect::new(x0, y0, x1 - x0 + 1, y1 - y0 + 1))
+    }
+    /// Copy of the cells inside `rect` (clipped to the buffer), moved to the origin.
+    pub fn crop(&self, rect: Rect) -> ScreenBuffer {
+        let rect = rect.clamp_to(self.bounds());
+        let mut out = ScreenBuffer::new(rect.w, rect.h);
+        out.blank = self.blank;
+        out.trailing_newline = self.trailing_newline;
+        out.tty_mode = self.tty_mode;
+        out.links = self.links.clone();
+        out.hyperlinks = self.hyperlinks;
+        out.widths = self.widths.clone();
+        for y in 0..rect.h {
+            for x in 0..rect.w {
+                let idx = out.index(x, y);
+                out.cells[idx] = self.cells[self.index(rect.x + x, rect.y + y)];
+            }
+        }
+        out
+    }
+    /// Text of the cells inside `rect`, one line per row with trailing spaces trimmed.
+    pub fn region_text(&self, rect: Rect) -> String {
+        let rect = rect.clamp_to(self.bounds());
+        let lines: Vec<String> = (rect.y..rect.bottom())
+            .map(|y| {
+                let line: String = (rect.x..rect.right())
+                    .map(|x| self.cells[self.index(x, y)].ch)
+                    .filter(|&ch| ch != CONTINUATION)
+                    .collect();
+                line.trim_end_matches(' ').to_string()
+            })
+            .collect();
+        lines.join("\n")
+    }
+    /// Cells where `other` differs from `self` in char, colors or link target, with
+    /// `other`'s char and colors: the updates that turn this frame into `other`.
+    /// Links are not carried by the patch, so patched cells come out unlinked; redo
+    /// them with `put_link`. Panics if the sizes differ.
+    pub fn diff(&self, other: &ScreenBuffer) -> Vec<(usize, usize, Cell)> {
+        assert_eq!(
+            (self.width, self.height),
+            (other.width, other.height),
+            "diffing buffers of different sizes"
+        );
+        let url = |buf: &ScreenBuffer, cell: &Cell| cell.link.map(|i| buf.links[i].clone());
+        self.cells
+            .iter()
+            .zip(&other.cells)
+            .enumerate()
+            .filter(|(_, (a, b))| {
+                (a.ch, a.fg, a.bg) != (b.ch, b.fg, b.bg) || url(self, a) != url(other, b)
+            })
+            .map(|(i, (_, b))| {
+                let cell = Cell { link: None, ..*b };
+                (i % self.width, i / self.width, cell)
+            })
+            .collect()
+    }
+    /// Applies updates as produced by `diff`; ones outside the buffer are ignored.
+    pub fn apply_patch(&mut self, patch: &[(usize, usize, Cell)]) {
+        for &(x, y, cell) in patch {
+            self.put_cell(x, y, Cell { link: None, ..cell });
+        }
+    }
+    pub fn draw_line(&mut self, x0: usize, y0: usize, x1: usize, y1: usize, ch: char) {
+        bresenham(x0, y0, x1, y1, |x, y| self.put_char(x, y, ch));
+    }
+    /// Fills every cell on or inside the triangle, clipped to the buffer.
+    pub fn fill_triangle(&mut self, p0: Point, p1: Point, p2: Point, ch: char) {
+        // > 0 when `p` lies left of the edge a -> b; i128 so vertices near
+        // `usize::MAX` keep their sign
+        let edge = |a: Point, b: Point, p: Point| {
+            let (ax, ay) = (a.x as i128, a.y as i128);
+            ((b.x as i128 - ax).saturating_mul(p.y as i128 - ay))
+                .saturating_sub((b.y as i128 - ay).saturating_mul(p.x as i128 - ax))
         };
-        // inner
-        let start_x = match align_inner {
-            Align::Left => start_x,
-            Align::Right => start_x + w.saturating_sub(visible_len),
+        let area = edge(p0, p1, p2);
+        let left = p0.x.min(p1.x).min(p2.x);
+        let top = p0.y.min(p1.y).min(p2.y);
+        let right = p0.x.max(p1.x).max(p2.x);
+        let bottom = p0.y.max(p1.y).max(p2.y);
+        let area_box = Rect::new(
+            left,
+            top,
+            (right - left).saturating_add(1),
+            (bottom - top).saturating_add(1),
+        );
+        let Some(area_box) = area_box.intersect(self.bounds()) else {
+            return;
         };
-        if ui.draw {
-            for i in 0..w {
-                ui.buf.put_char(ui.cursor_x + i, ui.cursor_y, ' ');
+        for y in area_box.y..area_box.bottom() {
+            for x in area_box.x..area_box.right() {
+                let p = Point::new(x, y);
+                let weights = [edge(p1, p2, p), edge(p2, p0, p), edge(p0, p1, p)];
+                // either winding order counts as inside
+                let inside = if area >= 0 {
+                    weights.iter().all(|&w| w >= 0)
+                } else {
+                    weights.iter().all(|&w| w <= 0)
+                };
+                if inside {
+                    self.put_char(x, y, ch);
+                }
             }
-            ui.buf.write_str(start_x, ui.cursor_y, slice);
         }
-        ui.used_x = ui.used_x.max(w);
-        ui.advance(w, 1);
     }
-}
-pub trait UiElement {
-    fn render<T: DrawTarget>(&self, ui: &mut Ui<T>);
-}
+    /// Writes a `{width} {height}` header line followed by one line per row.
+    /// Frames can be concatenated into a recording; cells holding `'\n'` are not supported.
+    pub fn write_frame<W: Write>(&self, mut out: W) -> io::Result<()> {
+        let mut text = format!("{} {}\n", self.width, self.height);
+        for y in 0..self.height {
+            text.extend((0..self.width).map(|x| self.cells[self.index(x, y)].ch));
+            text.push('\n');
+        }
+        out.write_all(text.as_bytes())
+    }
+    /// Reads one frame written by `write_frame`, consuming nothing past it. Frames over
+    /// `MAX_FRAME_CELLS` cells are rejected as `InvalidData`.
+    ///
+    /// Input is read a byte at a time, so wrap a file or socket in a `BufReader` (and
+    /// keep reading through it) rather than paying a syscall per byte.
+    pub fn read_frame<R: Read>(mut src: R) -> io::Result<ScreenBuffer> {
+        let invalid = |msg: &str| io::Error::new(io::ErrorKind::InvalidData, msg.to_string());
+        let header = String::from_utf8(read_line(&mut src)?)
+            .map_err(|_| invalid("frame header is not utf-8"))?;
+        let mut dims = header.split(' ').map(|n| n.parse::<usize>());
+        let (Some(Ok(width)), Some(Ok(height)), None) = (dims.next(), dims.next(), dims.next())
+        else {
+            return Err(invalid("malformed frame header"));
+        };
+        if width
+            .checked_mul(height)
+            .is_none_or(|cells| cells > MAX_FRAME_CELLS)
+        {
+            return Err(invalid("frame too large"));
+        }
+        let mut buf = ScreenBuffer::new(width, height);
+        for y in 0..height {
+            let line = String::from_utf8(read_line(&mut src)?)
+                .map_err(|_| invalid("frame row is not utf-8"))?;
+            if line.chars().count() != width {
+                return Err(invalid("frame row does not match the header width"));
+            }
+            for (x, ch) in line.chars().enumerate() {
+                buf.put_char(x, y, ch);
+            }
+        }
+        Ok(buf)
+    }
+}
+pub struct Player {
+    frames: Vec<ScreenBuffer>,
+    frame_duration: Duration,
+    position: usize,
+}
+impl Player {
+    pub fn from_reader<R: Read>(mut src: R, frame_duration: Duration) -> io::Result<Self> {
+        let mut recording = Vec::new();
+        src.read_to_end(&mut recording)?;
+        let mut rest = recording.as_slice();
+        let mut frames = Vec::new();
+        while !rest.is_empty() {
+            frames.push(ScreenBuffer::read_frame(&mut rest)?);
+        }
+        Ok(Self {
+            frames,
+            frame_duration,
+            position: 0,
+        })
+    }
+    pub fn len(&self) -> usize {
+        self.frames.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.frames.is_empty()
+    }
+    pub fn rewind(&mut self) {
+        self.position = 0;
+    }
+    /// Flushes the next frame, returns `None` once the recording is exhausted.
+    pub fn step(&mut self) -> Option<&ScreenBuffer> {
+        let frame = self.frames.get(self.position)?;
+        frame.flush();
+        self.position += 1;
+        Some(frame)
+    }
+    /// Plays the remaining frames, `speed` 2.0 plays twice as fast. A `speed` that isn't
+    /// a positive, finite number plays nothing.
+    pub fn play(&mut self, speed: f64) {
+        if !(speed.is_finite() && speed > 0.0) {
+            return;
+        }
+        let delay = Duration::try_from_secs_f64(self.frame_duration.as_secs_f64() / speed)
+            .unwrap_or(Duration::MAX);
+        if self.step().is_none() {
+            return;
+        }
+        // the delay goes between frames, none after the last
+        while self.position < self.frames.len() {
+            thread::sleep(delay);
+            self.step();
+        }
+    }
+}
+/// Sets the terminal window/tab title with an OSC 0 sequence. Control chars in `title`
+/// are dropped so they can't end the sequence early.
+///
+/// There's no portable way to read the current title back, so it can't be restored
+/// afterwards; set a sensible title again on exit if needed.
+pub fn set_terminal_title<W: Write>(mut out: W, title: &str) -> io::Result<()> {
+    let title: String = title.chars().filter(|c| !c.is_control()).collect();
+    write!(out, "\x1B]0;{title}\x07")?;
+    out.flush()
+}
+// reads up to and excluding the next `\n` one byte at a time, so nothing after it is consumed
+fn read_line<R: Read>(src: &mut R) -> io::Result<Vec<u8>> {
+    let mut line = Vec::new();
+    let mut byte = [0u8];
+    loop {
+        src.read_exact(&mut byte)?;
+        if byte[0] == b'\n' {
+            return Ok(line);
+        }
+        line.push(byte[0]);
+    }
+}
+fn bresenham(x0: usize, y0: usize, x1: usize, y1: usize, mut plot: impl FnMut(usize, usize)) {
+    let (mut x, mut y) = (x0 as isize, y0 as isize);
+    let (x1, y1) = (x1 as isize, y1 as isize);
+    let dx = (x1 - x).abs();
+    let dy = -(y1 - y).abs();
+    let sx = if x < x1 { 1 } else { -1 };
+    let sy = if y < y1 { 1 } else { -1 };
+    let mut err = dx + dy;
+    loop {
+        plot(x as usize, y as usize);
+        if x == x1 && y == y1 {
+            break;
+        }
+        let e2 = 2 * err;
+        if e2 >= dy {
+            err += dy;
+            x += sx;
+        }
+        if e2 <= dx {
+            err += dx;
+            y += sy;
+        }
+    }
+}
+impl fmt::Display for ScreenBuffer {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(&self.to_string_with_sep("\n"))
+    }
+}
+impl DrawTarget for ScreenBuffer {
+    fn clear(&mut self) {
+        for cell in &mut self.cells {
+            *cell = Cell::default();
+        }
+        self.links.clear();
+    }
+    fn put_char(&mut self, x: usize, y: usize, ch: char) {
+        self.put_cell(x, y, Cell::plain(ch));
+    }
+    fn put_char_fg(&mut self, x: usize, y: usize, ch: char, color: Color) {
+        self.put_char_styled(x, y, ch, Some(color), None);
+    }
+    fn put_char_bg(&mut self, x: usize, y: usize, ch: char, color: Color) {
+        self.put_char_styled(x, y, ch, None, Some(color));
+    }
+    fn put_char_styled(
+        &mut self,
+        x: usize,
+        y: usize,
+        ch: char,
+        fg: Option<Color>,
+        bg: Option<Color>,
+    ) {
+        let link = None;
+        self.put_cell(x, y, Cell { ch, fg, bg, link });
+    }
+    fn write_str(&mut self, x: usize, y: usize, text: &str) {
+        if y >= self.height {
+            return;
+        }
+        // bounds are checked once per char here, so the row is indexed directly
+        let row = self.index(0, y);
+        let mut px = x;
+        for ch in text.chars() {
+            // cells hold a single char, so zero-width marks are dropped
+            let w = self.char_width(ch);
+            if w == 0 {
+                continue;
+            }
+            if px.saturating_add(w) > self.width {
+                return;
+            }
+            self.cells[row + px] = Cell::plain(ch);
+            for cell in &mut self.cells[row + px + 1..row + px + w] {
+                *cell = Cell::plain(CONTINUATION);
+            }
+            px += w;
+        }
+    }
+    fn flush(&self) {
+        self.write_screen(io::stdout().lock(), self.ansi()).unwrap();
+    }
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+    fn char_width(&self, ch: char) -> usize {
+        if self.widths.is_empty() {
+            return text::char_width(ch);
+        }
+        match self.widths.get(&ch) {
+            Some(&w) => w,
+            None => text::char_width(ch),
+        }
+    }
+}
+/// A fixed-width buffer that grows downwards as rows are written, for content taller
+/// than the screen. Its height is reported as `max_height`, rows are only allocated
+/// once written, and `view` picks the rows to show.
+pub struct VirtualBuffer {
+    width: usize,
+    max_height: usize,
+    rows: Vec<Vec<Cell>>,
+}
+impl VirtualBuffer {
+    pub fn new(width: usize, max_height: usize) -> Self {
+        Self {
+            width,
+            max_height,
+            rows: Vec::new(),
+        }
+    }
+    /// Rows written so far, up to the lowest one touched.
+    pub fn len(&self) -> usize {
+        self.rows.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.rows.is_empty()
+    }
+    /// Up to `height` rows starting at row `top`; fewer near the end.
+    pub fn view(&self, top: usize, height: usize) -> impl Iterator<Item = &[Cell]> {
+        self.rows.iter().skip(top).take(height).map(Vec::as_slice)
+    }
+}
+impl DrawTarget for VirtualBuffer {
+    fn clear(&mut self) {
+        self.rows.clear();
+    }
+    fn put_char(&mut self, x: usize, y: usize, ch: char) {
+        // rows past `max_height` are dropped rather than grown into
+        if x >= self.width || y >= self.max_height {
+            return;
+        }
+        if y >= self.rows.len() {
+            self.rows.resize(y + 1, vec![Cell::default(); self.width]);
+        }
+        self.rows[y][x] = Cell::plain(ch);
+    }
+    fn flush(&self) {
+        let mut out = io::stdout().lock();
+        for row in &self.rows {
+            let line: String = row
+                .iter()
+                .map(Cell::ch)
+                .filter(|&ch| ch != CONTINUATION)
+                .collect();
+            writeln!(out, "{line}").unwrap();
+        }
+        out.flush().unwrap();
+    }
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.max_height)
+    }
+}
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Point {
+    pub x: usize,
+    pub y: usize,
+}
+impl Point {
+    pub fn new(x: usize, y: usize) -> Self {
+        Self { x, y }
+    }
+}
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub struct Rect {
+    pub x: usize,
+    pub y: usize,
+    pub w: usize,
+    pub h: usize,
+}
+impl Rect {
+    pub fn new(x: usize, y: usize, w: usize, h: usize) -> Self {
+        Self { x, y, w, h }
+    }
+    // exclusive edges
+    pub fn right(&self) -> usize {
+        self.x.saturating_add(self.w)
+    }
+    pub fn bottom(&self) -> usize {
+        self.y.saturating_add(self.h)
+    }
+    pub fn is_empty(&self) -> bool {
+        self.w == 0 || self.h == 0
+    }
+    pub fn contains(&self, p: Point) -> bool {
+        p.x >= self.x && p.x < self.right() && p.y >= self.y && p.y < self.bottom()
+    }
+    pub fn intersect(&self, other: Rect) -> Option<Rect> {
+        let x = self.x.max(other.x);
+        let y = self.y.max(other.y);
+        let right = self.right().min(other.right());
+        let bottom = self.bottom().min(other.bottom());
+        if x < right && y < bottom {
+            Some(Rect::new(x, y, right - x, bottom - y))
+        } else {
+            None
+        }
+    }
+    /// Like `intersect`, but yields an empty rect inside `bounds` when they don't overlap.
+    pub fn clamp_to(&self, bounds: Rect) -> Rect {
+        self.intersect(bounds).unwrap_or(Rect::new(
+            self.x.clamp(bounds.x, bounds.right()),
+            self.y.clamp(bounds.y, bounds.bottom()),
+            0,
+            0,
+        ))
+    }
+}
+/// Whether `ScreenBuffer::flush` writes escape sequences: clearing the screen and
+/// homing the cursor, colors and hyperlinks.
+#[derive(Copy, Clone)]
+pub enum TtyMode {
+    /// Escapes only when stdout is a terminal, plain text when piped.
+    Auto,
+    /// Always escapes, e.g. for a terminal stdout isn't detected as.
+    Ansi,
+    /// Never escapes, just the text.
+    Plain,
+}
+// lines drawn between and around grid cells
+#[derive(Copy, Clone)]
+pub enum GridLines {
+    None,
+    /// Separators between cells only.
+    Inner,
+    /// A box around every cell, neighbours sharing their edges.
+    Cells,
+}
+// order in which `UiGrid::cell` fills the grid
+#[derive(Copy, Clone)]
+pub enum GridFill {
+    RowMajor,
+    /// Down each of `rows` rows first, then on to the next column; the grid's `cols`
+    /// is then given by the cell count.
+    ColumnMajor {
+        rows: usize,
+    },
+}
+#[derive(Copy, Clone)]
+pub enum BorderKind {
+    Full,
+    Sides(Sides),
+    No,
+}
+#[derive(Copy, Clone, PartialEq, Eq)]
+pub struct Sides(u8);
+impl Sides {
+    pub const NONE: Sides = Sides(0);
+    pub const TOP: Sides = Sides(1);
+    pub const RIGHT: Sides = Sides(2);
+    pub const BOTTOM: Sides = Sides(4);
+    pub const LEFT: Sides = Sides(8);
+    pub const ALL: Sides = Sides(15);
+
+    pub fn contains(self, other: Sides) -> bool {
+        self.0 & other.0 == other.0
+    }
+}
+impl std::ops::BitOr for Sides {
+    type Output = Sides;
+    fn bitor(self, rhs: Sides) -> Sides {
+        Sides(self.0 | rhs.0)
+    }
+}
+#[derive(Copy, Clone)]
+enum LayoutKind {
+    Vertical,
+    Horizontal,
+}
+pub struct UiGrid<'a, 'b, T>
+where
+    T: DrawTarget,
+{
+    parent: &'b mut Ui<'a, T>,
+    start_x: usize,
+    start_y: usize,
+    cols: usize,
+    fill: GridFill,
+    spacing: usize,
+    spacing_inner: usize,
+    cell_idx: usize,
+    max_col_width: Vec<usize>,
+    max_row_height: Vec<usize>,
+    draw: bool,
+}
+impl<'a, 'b, T> UiGrid<'a, 'b, T>
+where
+    T: DrawTarget,
+{
+    /// True during the grid's first, non-drawing pass. Cell closures run once per pass,
+    /// so side effects that must happen once can check this.
+    pub fn is_measuring(&self) -> bool {
+        !self.draw
+    }
+    pub fn cell(&mut self, f: impl Fn(&mut Ui<T>)) {
+        let (row, col) = match self.fill {
+            GridFill::RowMajor => {
+                let cols = self.cols.max(1);
+                (self.cell_idx / cols, self.cell_idx % cols)
+            }
+            GridFill::ColumnMajor { rows } => {
+                let rows = rows.max(1);
+                (self.cell_idx % rows, self.cell_idx / rows)
+            }
+        };
+
+        if self.max_col_width.len() <= col {
+            self.max_col_width.resize(col + 1, 0);
+        }
+
+        if self.max_row_height.len() <= row {
+            self.max_row_height.resize(row + 1, 0);
+        }
+
+        let start_x = self.start_x
+            + self.max_col_width[..col].iter().sum::<usize>()
+            + col * self.spacing_inner;
+        let start_y = self.start_y
+            + self.max_row_height[..row].iter().sum::<usize>()
+            + row * self.spacing_inner;
+
+        let mut cell_ui = Ui {
+            buf: self.parent.buf,
+            cursor_x: start_x,
+            cursor_y: start_y,
+            max_x: start_x,
+            max_y: start_y,
+            available_x: Some(self.max_col_width[col]),
+            available_y: Some(self.max_row_height[row]),
+            used_x: 0,
+            used_y: 0,
+            layout: LayoutKind::Horizontal,
+            spacing: self.spacing,
+            draw: self.draw,
+            clip: self.parent.clip,
+            scroll_y: self.parent.scroll_y,
+            unbounded: self.parent.unbounded,
+            batch_open: false,
+            a11y: self.parent.a11y.as_deref_mut(),
+        };
+        f(&mut cell_ui);
+        let used_w = cell_ui.max_x - start_x;
+        self.max_col_width[col] = self.max_col_width[col].max(used_w);
+
+        let used_h = cell_ui.max_y - start_y;
+        self.max_row_height[row] = self.max_row_height[row].max(used_h);
+
+        self.cell_idx += 1;
+    }
+}
+pub struct Label<'a> {
+    text: &'a str,
+    width: Length,
+    align_inner: Align,
+    align_outer: Align,
+    direction: Direction,
+    wrap: WrapMode,
+}
+impl<'a> From<&'a String> for Label<'a> {
+    fn from(value: &'a String) -> Self {
+        Self {
+            text: value,
+            width: Length::Auto,
+            align_inner: Align::Left,
+            align_outer: Align::Left,
+            direction: Direction::Ltr,
+            wrap: WrapMode::Truncate,
+        }
+    }
+}
+impl<'a> From<&'a str> for Label<'a> {
+    fn from(value: &'a str) -> Self {
+        Self {
+            text: value,
+            width: Length::Auto,
+            align_inner: Align::Left,
+            align_outer: Align::Left,
+            direction: Direction::Ltr,
+            wrap: WrapMode::Truncate,
+        }
+    }
+}
+impl<'a> Label<'a> {
+    /// A width of 0 means the text's own width, as with no width at all.
+    pub fn with_width(mut self, width: usize) -> Self {
+        self.width = if width > 0 {
+            Length::Chars(width)
+        } else {
+            Length::Auto
+        };
+        self
+    }
+    pub fn with_length(mut self, length: Length) -> Self {
+        self.width = length;
+        self
+    }
+    pub fn align_inner(mut self, align_inner: Align) -> Self {
+        self.align_inner = align_inner;
+        self
+    }
+    pub fn align_outer(mut self, align_outer: Align) -> Self {
+        self.align_outer = align_outer;
+        self
+    }
+    pub fn direction(mut self, direction: Direction) -> Self {
+        self.direction = direction;
+        self
+    }
+    pub fn wrap(mut self, wrap: WrapMode) -> Self {
+        self.wrap = wrap;
+        self
+    }
+}
+impl<'a> UiElement for Label<'a> {
+    fn render<T: DrawTarget>(&self, ui: &mut Ui<T>) {
+        let text = self.text;
+        let align_inner = &self.align_inner;
+        let align_outer = &self.align_outer;
+
+        let len = ui.text_width(text);
+        // a length resolving to nothing falls back to the text's own width
+        let w = match resolve(self.width, ui.available_x) {
+            0 => len,
+            w => w,
+        };
+
+        if let WrapMode::Wrap = self.wrap
+            && len > w
+        {
+            let rows = wrap_words(text, w, |s| ui.text_width(s)).len();
+            let rect = Rect::new(ui.cursor_x, ui.cursor_y, w, rows);
+            ui.record_a11y(rect, A11yRole::Label, text);
+            ui.paragraph(text, w, Justify::Left, self.align_inner);
+            return;
+        }
+        let ellipsized;
+        let slice = match self.wrap {
+            WrapMode::Ellipsis if len > w && w > 0 => {
+                ellipsized = format!("{}…", ui.truncate_to_width(text, w - 1));
+                ellipsized.as_str()
+            }
+            _ => ui.truncate_to_width(text, w),
+        };
+        let visible_len = ui.text_width(slice);
+        // outer
+        let start_x = if let Some(avail_x) = ui.available_x {
+            match align_outer {
+                Align::Left => ui.cursor_x,
+                Align::Center => ui.cursor_x.saturating_add(avail_x.saturating_sub(w) / 2),
+                Align::Right => ui.cursor_x.saturating_add(avail_x.saturating_sub(w)),
+            }
+        } else {
+            // no right border known, that we can align to
+            ui.cursor_x
+        };
+        // inner
+        let start_x = match align_inner {
+            Align::Left => start_x,
+            Align::Center => start_x.saturating_add(w.saturating_sub(visible_len) / 2),
+            Align::Right => start_x.saturating_add(w.saturating_sub(visible_len)),
+        };
+        if ui.draw {
+            let (bound_x, _) = ui.bounds();
+            for i in 0..w.min(bound_x.saturating_sub(ui.cursor_x)) {
+                ui.put_char(ui.cursor_x + i, ui.cursor_y, ' ');
+            }
+            match self.direction {
+                Direction::Ltr => ui.write_str(start_x, ui.cursor_y, slice),
+                // logical order kept, each char placed left of the previous one
+                Direction::Rtl => {
+                    let mut x = start_x.saturating_add(visible_len);
+                    for ch in slice.chars() {
+                        let ch_width = ui.buf.char_width(ch);
+                        if ch_width > 0 {
+                            x -= ch_width;
+                            ui.put_char(x, ui.cursor_y, ch);
+                        }
+                    }
+                }
+            }
+        }
+        let rect = Rect::new(ui.cursor_x, ui.cursor_y, w, 1);
+        ui.record_a11y(rect, A11yRole::Label, text);
+        ui.used_x = ui.used_x.max(w);
+        ui.advance(w, 1);
+    }
+}
+pub trait UiElement {
+    fn render<T: DrawTarget>(&self, ui: &mut Ui<T>);
+}
+/// A size along one axis, resolved against the available space with [`resolve`].
+#[derive(Copy, Clone, Debug, PartialEq)]
+pub enum Length {
+    Chars(usize),
+    /// Percent of the available space, 0..=100.
+    Percent(u16),
+    Fill,
+    Auto,
+}
+/// Resolves `length` against `available`:
+/// - `Chars(n)` is always `n`, even if that's more than is available.
+/// - `Percent(p)` is `p`% of `available`, rounded down; 0 when `available` is `None`.
+/// - `Fill` is all of `available`; 0 when `available` is `None`.
+/// - `Auto` is always 0, the caller sizes to its content.
+///
+/// A result of 0 means "no size imposed", so callers fall back to their content size.
+pub fn resolve(length: Length, available: Option<usize>) -> usize {
+    match length {
+        Length::Chars(n) => n,
+        Length::Percent(p) => available.map_or(0, |a| a * p.min(100) as usize / 100),
+        Length::Fill => available.unwrap_or(0),
+        Length::Auto => 0,
+    }
+}
 pub enum StretchHint {
     Full,
     Compact,
+    /// Stretch to the available width only while the content is narrower than
+    /// `threshold` (0.0..=1.0) of it, otherwise stay compact.
+    Auto {
+        threshold: f64,
+    },
 }
+#[derive(Copy, Clone)]
 pub enum Align {
     Left,
+    Center,
     Right,
 }
+// how numbers wider than their field are rendered
+pub enum Overflow {
+    Clamp,
+    Hash,
+    Ellipsis,
+}
+// what a label does with text wider than its field
+pub enum WrapMode {
+    Truncate,
+    Ellipsis,
+    /// Word-wraps like `paragraph` within the field width, over as many rows as needed.
+    Wrap,
+}
+pub enum Corner {
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+}
+pub enum Direction {
+    Ltr,
+    Rtl,
+}
+pub enum Justify {
+    Left,
+    Full,
+}
+// double-line box: top-left, top-right, bottom-left, bottom-right, horizontal, vertical
+const DOUBLE_BOX: [char; 6] = ['╔', '╗', '╚', '╝', '═', '║'];
+// a single-line divider meeting a double border: left, right, top, bottom
+const SINGLE_INTO_DOUBLE: [char; 4] = ['╟', '╢', '╤', '╧'];
+/// What a widget recorded by [`Ui::capture_a11y`] is.
+#[derive(Copy, Clone, Debug, PartialEq, Eq)]
+pub enum A11yRole {
+    Label,
+    /// A `number_spin`, with its value as the text.
+    SpinButton,
+}
+/// A widget's area in layout coordinates, its role and its text.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct A11yNode {
+    pub rect: Rect,
+    pub role: A11yRole,
+    pub text: String,
+}
 pub struct Ui<'a, T: DrawTarget> {
     buf: &'a mut T,
     cursor_x: usize,
@@ -357,12 +1338,22 @@ pub struct Ui<'a, T: DrawTarget> {
     layout: LayoutKind,
     spacing: usize,
     draw: bool,
+    // in screen coordinates
+    clip: Option<Rect>,
+    // rows scrolled out above the screen; layout y minus this is the screen y
+    scroll_y: usize,
+    // measuring passes don't stop at the target's edges
+    unbounded: bool,
+    // the frame's draw batch is open; only ever set on the root `Ui`
+    batch_open: bool,
+    a11y: Option<&'a mut Vec<A11yNode>>,
 }
 impl<'a, T> Ui<'a, T>
 where
     T: DrawTarget,
 {
     pub fn new(buf: &'a mut T, x: usize, y: usize) -> Self {
+        buf.begin_batch();
         Ui {
             buf,
             cursor_x: x,
@@ -376,12 +1367,42 @@ where
             layout: LayoutKind::Vertical,
             spacing: 0,
             draw: true,
+            clip: None,
+            scroll_y: 0,
+            unbounded: false,
+            batch_open: true,
+            a11y: None,
+        }
+    }
+    /// Records the labels and spin buttons drawn from now on into `log`, for building
+    /// an accessibility tree once the frame is rendered. Other widgets aren't recorded.
+    pub fn capture_a11y(&mut self, log: &'a mut Vec<A11yNode>) {
+        self.a11y = Some(log);
+    }
+    // measuring passes don't draw and aren't recorded
+    fn record_a11y(&mut self, rect: Rect, role: A11yRole, text: &str) {
+        if self.draw
+            && let Some(log) = self.a11y.as_deref_mut()
+        {
+            log.push(A11yNode {
+                rect,
+                role,
+                text: text.to_string(),
+            });
         }
     }
     pub fn flush(&mut self) {
+        if self.batch_open {
+            self.buf.end_batch();
+            self.batch_open = false;
+        }
         self.buf.flush();
     }
     pub fn clear(&mut self) {
+        if !self.batch_open {
+            self.buf.begin_batch();
+            self.batch_open = true;
+        }
         self.buf.clear();
         self.cursor_x = 0;
         self.cursor_y = 0;
@@ -398,8 +1419,16 @@ where
         E::render(&ui_element, self);
     }
     fn advance(&mut self, w: usize, h: usize) {
-        self.max_x = self.max_x.max(self.cursor_x + w);
-        self.max_y = self.max_y.max(self.cursor_y + h);
+        // saturate at the target's edges so measurements stay meaningful
+        let (bound_x, bound_y) = if self.unbounded {
+            (usize::MAX, usize::MAX)
+        } else {
+            self.bounds()
+        };
+        let bound_x = bound_x.max(self.cursor_x);
+        let bound_y = bound_y.max(self.cursor_y);
+        self.max_x = self.max_x.max(self.cursor_x.saturating_add(w).min(bound_x));
+        self.max_y = self.max_y.max(self.cursor_y.saturating_add(h).min(bound_y));
 
         match self.layout {
             LayoutKind::Vertical => {
@@ -407,35 +1436,53 @@ where
                 if let Some(avail_y) = self.available_y {
                     self.available_y = avail_y.checked_sub(h);
                 }
-                self.cursor_y += h + self.spacing;
+                self.cursor_y = self
+                    .cursor_y
+                    .saturating_add(h)
+                    .saturating_add(self.spacing)
+                    .min(bound_y);
             }
             LayoutKind::Horizontal => {
                 self.used_y = self.used_y.max(h);
                 if let Some(avail_x) = self.available_x {
                     self.available_x = avail_x.checked_sub(w);
                 }
-                self.cursor_x += w + self.spacing;
+                self.cursor_x = self
+                    .cursor_x
+                    .saturating_add(w)
+                    .saturating_add(self.spacing)
+                    .min(bound_x);
             }
         }
     }
-    fn child(&mut self, layout: LayoutKind, spacing: usize, f: impl FnOnce(&mut Ui<T>)) {
-        let start_x = self.cursor_x;
-        let start_y = self.cursor_y;
-
-        let mut child = Ui {
+    // a sub-ui starting at (x, y) that inherits availability, spacing, drawing and clipping
+    fn nested(&mut self, x: usize, y: usize, layout: LayoutKind) -> Ui<'_, T> {
+        Ui {
             buf: self.buf,
-            cursor_x: start_x,
-            cursor_y: start_y,
-            max_x: start_x,
-            max_y: start_y,
+            cursor_x: x,
+            cursor_y: y,
+            max_x: x,
+            max_y: y,
             available_x: self.available_x,
             available_y: self.available_y,
             used_x: 0,
             used_y: 0,
             layout,
-            spacing,
+            spacing: self.spacing,
             draw: self.draw,
-        };
+            clip: self.clip,
+            scroll_y: self.scroll_y,
+            unbounded: self.unbounded,
+            batch_open: false,
+            a11y: self.a11y.as_deref_mut(),
+        }
+    }
+    fn child(&mut self, layout: LayoutKind, spacing: usize, f: impl FnOnce(&mut Ui<T>)) {
+        let start_x = self.cursor_x;
+        let start_y = self.cursor_y;
+
+        let mut child = self.nested(start_x, start_y, layout);
+        child.spacing = spacing;
         f(&mut child);
 
         let used_w = match child.layout {
@@ -448,24 +1495,171 @@ where
         };
         self.advance(used_w, used_h);
     }
-    fn draw_frame(&mut self, x: usize, y: usize, w: usize, h: usize) {
-        if !self.draw {
+    fn draw_frame(&mut self, x: usize, y: usize, w: usize, h: usize, sides: Sides) {
+        if !self.draw || w == 0 || h == 0 {
+            return;
+        }
+        let (right, bottom) = (x.saturating_add(w - 1), y.saturating_add(h - 1));
+        // edges only run as far as the target reaches
+        let (bound_x, bound_y) = self.bounds();
+        for dx in 0..w.min(bound_x.saturating_sub(x)) {
+            if sides.contains(Sides::TOP) {
+                self.put_char(x + dx, y, '-');
+            }
+            if sides.contains(Sides::BOTTOM) {
+                self.put_char(x + dx, bottom, '-');
+            }
+        }
+        for dy in 0..h.min(bound_y.saturating_sub(y)) {
+            if sides.contains(Sides::LEFT) {
+                self.put_char(x, y + dy, '|');
+            }
+            if sides.contains(Sides::RIGHT) {
+                self.put_char(right, y + dy, '|');
+            }
+        }
+
+        // corners only where two drawn edges meet
+        let corners = [
+            (x, y, Sides::TOP | Sides::LEFT),
+            (right, y, Sides::TOP | Sides::RIGHT),
+            (x, bottom, Sides::BOTTOM | Sides::LEFT),
+            (right, bottom, Sides::BOTTOM | Sides::RIGHT),
+        ];
+        for (cx, cy, edges) in corners {
+            if sides.contains(edges) {
+                self.put_char(cx, cy, '+');
+            }
+        }
+    }
+    // width of `s` as the target draws it, so its `char_width` overrides are honored
+    fn text_width(&self, s: &str) -> usize {
+        s.chars().map(|ch| self.buf.char_width(ch)).sum()
+    }
+    // `text::truncate_to_width` measured by the target
+    fn truncate_to_width<'s>(&self, s: &'s str, w: usize) -> &'s str {
+        let mut used = 0;
+        for (i, ch) in s.char_indices() {
+            used += self.buf.char_width(ch);
+            if used > w {
+                return &s[..i];
+            }
+        }
+        s
+    }
+    // the target's size in layout coordinates
+    fn bounds(&self) -> (usize, usize) {
+        let (w, h) = self.buf.dimensions();
+        (w, h.saturating_add(self.scroll_y))
+    }
+    // `rect` is in layout coordinates
+    fn clip_to(&mut self, rect: Rect) {
+        let top = rect.y.saturating_sub(self.scroll_y);
+        let bottom = rect.bottom().saturating_sub(self.scroll_y);
+        let rect = Rect::new(rect.x, top, rect.w, bottom - top);
+        self.clip = Some(match self.clip {
+            Some(clip) => rect.clamp_to(clip),
+            None => rect,
+        });
+    }
+    fn visible(&self, x: usize, y: usize) -> bool {
+        self.draw
+            && y >= self.scroll_y
+            && self
+                .clip
+                .is_none_or(|clip| clip.contains(Point::new(x, y - self.scroll_y)))
+    }
+    // true when all of `x..x + w` on row `y` may be drawn
+    fn visible_span(&self, x: usize, y: usize, w: usize) -> bool {
+        if !self.draw || y < self.scroll_y {
+            return false;
+        }
+        let span = Rect::new(x, y - self.scroll_y, w, 1);
+        self.clip
+            .is_none_or(|clip| span.intersect(clip) == Some(span))
+    }
+    fn put_char(&mut self, x: usize, y: usize, ch: char) {
+        if self.visible(x, y) {
+            self.buf.put_char(x, y - self.scroll_y, ch);
+        }
+    }
+    fn put_char_fg(&mut self, x: usize, y: usize, ch: char, color: Color) {
+        if self.visible(x, y) {
+            self.buf.put_char_fg(x, y - self.scroll_y, ch, color);
+        }
+    }
+    fn write_str(&mut self, x: usize, y: usize, text: &str) {
+        if self.clip.is_none() && self.scroll_y == 0 {
+            if self.draw {
+                self.buf.write_str(x, y, text);
+            }
             return;
         }
-        let buf = &mut self.buf;
-        for dx in 0..w {
-            buf.put_char(x + dx, y, '-');
-            buf.put_char(x + dx, y + h - 1, '-');
+        let mut px = x;
+        for ch in text.chars() {
+            let w = self.buf.char_width(ch);
+            if w == 0 {
+                continue;
+            }
+            if self.visible_span(px, y, w) {
+                let sy = y - self.scroll_y;
+                self.buf.put_char(px, sy, ch);
+                for cx in px + 1..px + w {
+                    self.buf.put_char(cx, sy, CONTINUATION);
+                }
+            }
+            px += w;
         }
-        for dy in 0..h {
-            buf.put_char(x, y + dy, '|');
-            buf.put_char(x + w - 1, y + dy, '|');
+    }
+    fn put_canvas(&mut self, canvas: &BrailleCanvas) {
+        for cy in 0..canvas.height {
+            for cx in 0..canvas.width {
+                self.put_char(self.cursor_x + cx, self.cursor_y + cy, canvas.glyph(cx, cy));
+            }
         }
-
-        buf.put_char(x, y, '+');
-        buf.put_char(x + w - 1, y, '+');
-        buf.put_char(x, y + h - 1, '+');
-        buf.put_char(x + w - 1, y + h - 1, '+');
+    }
+    /// Runs `f` with overridden available space; afterwards the previous values
+    /// are restored, less whatever `f` consumed.
+    pub fn with_available(
+        &mut self,
+        w: Option<usize>,
+        h: Option<usize>,
+        f: impl FnOnce(&mut Ui<T>),
+    ) {
+        let (saved_x, saved_y) = (self.available_x, self.available_y);
+        let (start_x, start_y) = (self.cursor_x, self.cursor_y);
+        self.available_x = w;
+        self.available_y = h;
+        f(self);
+        let used_x = self.cursor_x.saturating_sub(start_x);
+        let used_y = self.cursor_y.saturating_sub(start_y);
+        self.available_x = saved_x.and_then(|a| a.checked_sub(used_x));
+        self.available_y = saved_y.and_then(|a| a.checked_sub(used_y));
+    }
+    pub fn is_horizontal(&self) -> bool {
+        matches!(self.layout, LayoutKind::Horizontal)
+    }
+    pub fn remaining(&self) -> (Option<usize>, Option<usize>) {
+        (self.available_x, self.available_y)
+    }
+    /// Moves the cursor to the absolute `column`, or the next multiple of it when already past.
+    pub fn tab_to(&mut self, column: usize) {
+        let target = if self.cursor_x <= column || column == 0 {
+            column.max(self.cursor_x)
+        } else {
+            self.cursor_x.div_ceil(column) * column
+        };
+        let (bound_x, _) = self.bounds();
+        let target = target.min(bound_x.max(self.cursor_x));
+        let w = target - self.cursor_x;
+        for i in 0..w {
+            self.put_char(self.cursor_x + i, self.cursor_y, ' ');
+        }
+        if let Some(avail_x) = self.available_x {
+            self.available_x = avail_x.checked_sub(w);
+        }
+        self.max_x = self.max_x.max(target);
+        self.cursor_x = target;
     }
     pub fn space(&mut self, amount: usize) {
         match self.layout {
@@ -473,50 +1667,205 @@ where
             LayoutKind::Horizontal => self.advance(amount, 0),
         }
     }
+    /// Size `f`'s content would take at the cursor, without drawing or moving the cursor.
+    pub fn measure(&mut self, f: impl FnOnce(&mut Ui<T>)) -> (usize, usize) {
+        let (x, y) = (self.cursor_x, self.cursor_y);
+        let mut probe = self.nested(x, y, self.layout);
+        probe.draw = false;
+        probe.unbounded = true;
+        f(&mut probe);
+        (probe.max_x - x, probe.max_y - y)
+    }
+    /// Rows `f`'s content would take; the height half of [`Ui::measure`].
+    pub fn measure_height(&mut self, f: impl FnOnce(&mut Ui<T>)) -> usize {
+        self.measure(f).1
+    }
+    // a vertical sub-ui at screen position (x, y), free of the layout's clip and scroll
+    fn absolute(&mut self, x: usize, y: usize) -> Ui<'_, T> {
+        let mut ui = self.nested(x, y, LayoutKind::Vertical);
+        ui.available_x = None;
+        ui.available_y = None;
+        ui.clip = None;
+        ui.scroll_y = 0;
+        ui
+    }
+    /// Draws `f` flush to a corner of the target, leaving the layout cursor untouched.
+    /// `f` runs twice, once to measure and once to draw, hence `Fn`.
+    pub fn anchor(&mut self, corner: Corner, f: impl Fn(&mut Ui<T>)) {
+        if !self.draw {
+            return;
+        }
+        let mut probe = self.absolute(0, 0);
+        let (w, h) = probe.measure(&f);
+
+        let (bound_x, bound_y) = self.buf.dimensions();
+        let right = bound_x.saturating_sub(w);
+        let bottom = bound_y.saturating_sub(h);
+        let (x, y) = match corner {
+            Corner::TopLeft => (0, 0),
+            Corner::TopRight => (right, 0),
+            Corner::BottomLeft => (0, bottom),
+            Corner::BottomRight => (right, bottom),
+        };
+        f(&mut self.absolute(x, y));
+    }
+    /// `counter`'s frame rate, e.g. `60 fps`, in the top-right corner of the target.
+    pub fn fps_overlay(&mut self, counter: &FpsCounter) {
+        let text = format!("{:.0} fps", counter.fps());
+        self.anchor(Corner::TopRight, |ui| ui.label(&text));
+    }
+    /// Runs `f` when `cond`; otherwise skips it, or with `reserve_when_hidden` takes up
+    /// the space it would have used so the layout doesn't shift when it's toggled.
+    pub fn show_if(&mut self, cond: bool, reserve_when_hidden: bool, f: impl FnOnce(&mut Ui<T>)) {
+        if cond {
+            f(self);
+        } else if reserve_when_hidden {
+            let (w, h) = self.measure(f);
+            self.reserve(w, h);
+        }
+    }
+    /// Runs `f` in a region of exactly `size`, clipped to it, and advances by `size`
+    /// whatever `f` actually used.
+    pub fn place(&mut self, size: (usize, usize), f: impl FnOnce(&mut Ui<T>)) {
+        let (x, y) = (self.cursor_x, self.cursor_y);
+        let mut region = self.nested(x, y, LayoutKind::Vertical);
+        region.available_x = Some(size.0);
+        region.available_y = Some(size.1);
+        region.clip_to(Rect::new(x, y, size.0, size.1));
+        f(&mut region);
+        self.advance(size.0, size.1);
+    }
+    /// Takes up a `w` x `h` region without drawing, e.g. for an overlay drawn later.
+    pub fn reserve(&mut self, w: usize, h: usize) {
+        self.advance(w, h);
+    }
     pub fn vertical(&mut self, f: impl FnOnce(&mut Ui<T>)) {
         self.child(LayoutKind::Vertical, self.spacing, f);
     }
     pub fn horizontal(&mut self, f: impl FnOnce(&mut Ui<T>)) {
         self.child(LayoutKind::Horizontal, self.spacing, f);
     }
-    pub fn grid(&mut self, cols: usize, spacing: usize, f: impl Fn(&mut UiGrid<T>)) {
+    /// Lays out cells in `cols` columns. `f` runs twice, once to measure and once to
+    /// draw; see `UiGrid::is_measuring`.
+    pub fn grid(
+        &mut self,
+        cols: usize,
+        spacing: usize,
+        lines: GridLines,
+        fill: GridFill,
+        f: impl Fn(&mut UiGrid<T>),
+    ) {
         let start_x = self.cursor_x;
         let start_y = self.cursor_y;
+        let draw = self.draw;
+        // a separator line sits in the middle of the gap between two cells,
+        // and a border `spacing` away from the outer cells
+        let (gap, margin) = match lines {
+            GridLines::None => (spacing, 0),
+            GridLines::Inner => (2 * spacing + 1, 0),
+            GridLines::Cells => (2 * spacing + 1, spacing + 1),
+        };
 
         let mut tmp_grid = UiGrid {
             spacing: self.spacing,
             parent: self,
-            start_x,
-            start_y,
+            start_x: start_x + margin,
+            start_y: start_y + margin,
             cols,
-            spacing_inner: spacing,
+            fill,
+            spacing_inner: gap,
             cell_idx: 0,
             max_col_width: vec![0; cols],
             max_row_height: vec![0],
             draw: false,
         };
         f(&mut tmp_grid);
-        let measured_max_col_width = tmp_grid.max_col_width;
-        let measured_max_row_height = tmp_grid.max_row_height;
+        // only rows and columns that actually received a cell count towards the size
+        let cells = tmp_grid.cell_idx;
+        let (rows, cols) = match fill {
+            GridFill::RowMajor => (cells.div_ceil(cols.max(1)), cols),
+            GridFill::ColumnMajor { rows } => (rows.min(cells), cells.div_ceil(rows.max(1))),
+        };
+        let mut measured_max_col_width = tmp_grid.max_col_width;
+        measured_max_col_width.resize(cols, 0);
+        let mut measured_max_row_height = tmp_grid.max_row_height;
+        measured_max_row_height.truncate(rows);
 
         let mut grid = UiGrid {
             spacing: self.spacing,
             parent: self,
-            start_x,
-            start_y,
+            start_x: start_x + margin,
+            start_y: start_y + margin,
             cols,
-            spacing_inner: spacing,
+            fill,
+            spacing_inner: gap,
             cell_idx: 0,
             max_col_width: measured_max_col_width,
             max_row_height: measured_max_row_height,
-            draw: true,
+            draw,
         };
         f(&mut grid);
 
-        let used_w = grid.max_col_width.iter().sum::<usize>()
-            + grid.spacing_inner * (cols.saturating_sub(1));
-        let used_h = grid.max_row_height.iter().sum::<usize>()
-            + grid.spacing_inner * grid.max_row_height.len().saturating_sub(1);
+        let col_widths = grid.max_col_width;
+        let row_heights = grid.max_row_height;
+        let span = |sizes: &[usize]| {
+            if sizes.is_empty() {
+                0
+            } else {
+                sizes.iter().sum::<usize>() + gap * (sizes.len() - 1) + 2 * margin
+            }
+        };
+        let used_w = span(&col_widths);
+        let used_h = span(&row_heights);
+        let drawn = !matches!(lines, GridLines::None);
+        if drawn && draw && used_w > 0 && used_h > 0 {
+            let outer = matches!(lines, GridLines::Cells);
+            let line_positions = |start: usize, sizes: &[usize], used: usize| {
+                let mut pos = start + margin;
+                let mut lines = Vec::new();
+                if outer {
+                    lines.push(start);
+                }
+                for size in sizes.iter().take(sizes.len().saturating_sub(1)) {
+                    pos += size + spacing;
+                    lines.push(pos);
+                    pos += spacing + 1;
+                }
+                if outer {
+                    lines.push(start + used - 1);
+                }
+                lines
+            };
+            let xs = line_positions(start_x, &col_widths, used_w);
+            let ys = line_positions(start_y, &row_heights, used_h);
+            let (right, bottom) = (start_x + used_w - 1, start_y + used_h - 1);
+            for &x in &xs {
+                for y in start_y..=bottom {
+                    self.put_char(x, y, '│');
+                }
+            }
+            for &y in &ys {
+                for x in start_x..=right {
+                    let ch = if xs.contains(&x) {
+                        // which arms of the crossing lines reach this point
+                        match (y > start_y, y < bottom, x > start_x, x < right) {
+                            (false, _, false, _) => '┌',
+                            (false, _, _, false) => '┐',
+                            (_, false, false, _) => '└',
+                            (_, false, _, false) => '┘',
+                            (false, ..) => '┬',
+                            (_, false, ..) => '┴',
+                            (_, _, false, _) => '├',
+                            (_, _, _, false) => '┤',
+                            _ => '┼',
+                        }
+                    } else {
+                        '─'
+                    };
+                    self.put_char(x, y, ch);
+                }
+            }
+        }
         self.advance(used_w, used_h);
     }
     pub fn frame(
@@ -524,14 +1873,20 @@ where
         padding: usize,
         border: BorderKind,
         stretch: StretchHint,
+        min_inner: (usize, usize),
         f: impl FnOnce(&mut Ui<T>),
     ) {
         let start_x = self.cursor_x;
         let start_y = self.cursor_y;
+        let pad2 = padding.saturating_mul(2);
+        let (inner_x, inner_y) = (
+            start_x.saturating_add(padding),
+            start_y.saturating_add(padding),
+        );
 
         let avail_x = if let Some(x) = self.available_x {
-            if x.saturating_sub(2 * padding) > 0 {
-                Some(x - 2 * padding)
+            if x.saturating_sub(pad2) > 0 {
+                Some(x - pad2)
             } else {
                 None
             }
@@ -539,8 +1894,8 @@ where
             None
         };
         let avail_y = if let Some(y) = self.available_y {
-            if y.saturating_sub(2 * padding) > 0 {
-                Some(y - 2 * padding)
+            if y.saturating_sub(pad2) > 0 {
+                Some(y - pad2)
             } else {
                 None
             }
@@ -549,10 +1904,10 @@ where
         };
         let mut child = Ui {
             buf: self.buf,
-            cursor_x: start_x + padding,
-            cursor_y: start_y + padding,
-            max_x: start_x + padding,
-            max_y: start_y + padding,
+            cursor_x: inner_x,
+            cursor_y: inner_y,
+            max_x: inner_x,
+            max_y: inner_y,
             // TODO: should depend on whether frame is compact or full not yet implemented
             available_x: avail_x,
             available_y: avail_y,
@@ -561,12 +1916,32 @@ where
             layout: LayoutKind::Vertical,
             spacing: self.spacing,
             draw: self.draw,
+            clip: self.clip,
+            scroll_y: self.scroll_y,
+            unbounded: self.unbounded,
+            batch_open: false,
+            a11y: self.a11y.as_deref_mut(),
         };
+        // a stretched frame with a known height clips whatever doesn't fit
+        let clip_h = match (&stretch, avail_y) {
+            (StretchHint::Full, Some(h)) => Some(h),
+            _ => None,
+        };
+        if let Some(h) = clip_h {
+            child.clip_to(Rect::new(0, inner_y, usize::MAX, h));
+        }
 
         f(&mut child);
+        let overflow = clip_h.is_some_and(|h| child.max_y > inner_y.saturating_add(h));
 
-        let mut used_w = child.max_x - start_x + padding;
-        let mut used_h = child.max_y - start_y + padding;
+        // keep at least `min_inner` so an empty frame doesn't collapse into its border
+        let inner_w = (child.max_x - inner_x).max(min_inner.0);
+        let mut inner_h = (child.max_y - inner_y).max(min_inner.1);
+        if let Some(h) = clip_h {
+            inner_h = inner_h.min(h);
+        }
+        let mut used_w = inner_w.saturating_add(pad2);
+        let mut used_h = inner_h.saturating_add(pad2);
 
         match stretch {
             StretchHint::Full => {
@@ -575,324 +1950,2469 @@ where
                 used_h = used_h.max(self.available_y.unwrap_or(0))
             }
             StretchHint::Compact => {}
+            StretchHint::Auto { threshold } => {
+                if let Some(avail) = self.available_x
+                    && (inner_w as f64) < threshold * avail as f64
+                {
+                    used_w = used_w.max(avail);
+                }
+            }
         }
 
         match border {
-            BorderKind::Full => self.draw_frame(start_x, start_y, used_w, used_h),
+            BorderKind::Full => self.draw_frame(start_x, start_y, used_w, used_h, Sides::ALL),
+            BorderKind::Sides(sides) => self.draw_frame(start_x, start_y, used_w, used_h, sides),
             BorderKind::No => {}
         }
+        let bottom_drawn = match border {
+            BorderKind::Full => true,
+            BorderKind::Sides(sides) => sides.contains(Sides::BOTTOM),
+            BorderKind::No => false,
+        };
+        if overflow && bottom_drawn && used_w >= 3 {
+            // more content below, marked next to the bottom-right corner
+            let (x, y) = (
+                start_x.saturating_add(used_w - 2),
+                start_y.saturating_add(used_h - 1),
+            );
+            self.put_char(x, y, '▼');
+        }
         self.advance(used_w, used_h);
     }
+    /// A bordered panel showing `inner_height` rows of `f`'s output from row `offset` on,
+    /// with a scrollbar on the right edge when `content_height` rows don't fit.
+    pub fn scroll_frame(
+        &mut self,
+        inner_height: usize,
+        offset: usize,
+        content_height: usize,
+        f: impl FnOnce(&mut Ui<T>),
+    ) {
+        let (start_x, start_y) = (self.cursor_x, self.cursor_y);
+        let (bound_x, _) = self.bounds();
+        let w = self.available_x.unwrap_or(bound_x.saturating_sub(start_x));
+        let h = inner_height + 2;
+        let inner_w = w.saturating_sub(2);
+        let offset = offset.min(content_height.saturating_sub(inner_height));
+
+        // lay the content out from its first row, shifted up by `offset` when drawn
+        let mut child = self.nested(start_x + 1, start_y + 1, LayoutKind::Vertical);
+        child.available_x = Some(inner_w);
+        child.available_y = None;
+        child.scroll_y += offset;
+        child.clip_to(Rect::new(
+            start_x + 1,
+            start_y + 1 + offset,
+            inner_w,
+            inner_height,
+        ));
+        f(&mut child);
+
+        self.draw_frame(start_x, start_y, w, h, Sides::ALL);
+        if content_height > inner_height && inner_height > 0 && w > 0 {
+            let thumb = (inner_height * inner_height / content_height).max(1);
+            let pos = offset * (inner_height - thumb) / (content_height - inner_height);
+            for dy in 0..inner_height {
+                let ch = if (pos..pos + thumb).contains(&dy) {
+                    '█'
+                } else {
+                    '░'
+                };
+                self.put_char(start_x + w - 1, start_y + 1 + dy, ch);
+            }
+        }
+        self.advance(w, h);
+    }
+    /// A bordered panel sized to `f`'s content but at most `max_w` x `max_h` including
+    /// the border. Content taller than that is shown from row `offset` on, with `▲`/`▼`
+    /// on the border when rows are hidden above/below.
+    /// `f` runs twice, once to measure and once to draw, hence `Fn`.
+    pub fn frame_bounded(
+        &mut self,
+        max_w: usize,
+        max_h: usize,
+        offset: usize,
+        f: impl Fn(&mut Ui<T>),
+    ) {
+        let (start_x, start_y) = (self.cursor_x, self.cursor_y);
+        let max_inner_w = max_w.saturating_sub(2);
+        let max_inner_h = max_h.saturating_sub(2);
+        let (content_w, content_h) = self.measure(|ui| {
+            ui.available_x = Some(max_inner_w);
+            ui.available_y = None;
+            f(ui)
+        });
+        let inner_w = content_w.min(max_inner_w);
+        let inner_h = content_h.min(max_inner_h);
+        let offset = offset.min(content_h.saturating_sub(inner_h));
+
+        let mut child = self.nested(start_x + 1, start_y + 1, LayoutKind::Vertical);
+        child.available_x = Some(inner_w);
+        child.available_y = None;
+        child.scroll_y += offset;
+        child.clip_to(Rect::new(
+            start_x + 1,
+            start_y + 1 + offset,
+            inner_w,
+            inner_h,
+        ));
+        f(&mut child);
+
+        let (w, h) = (inner_w + 2, inner_h + 2);
+        self.draw_frame(start_x, start_y, w, h, Sides::ALL);
+        if w >= 3 {
+            if offset > 0 {
+                self.put_char(start_x + w - 2, start_y, '▲');
+            }
+            if offset + inner_h < content_h {
+                self.put_char(start_x + w - 2, start_y + h - 1, '▼');
+            }
+        }
+        self.advance(w, h);
+    }
+    /// `f`'s content in a double-line border, with single-line dividers across the
+    /// inner rows in `divider_rows` (counted from the first inner row) joined to it.
+    /// The content should leave those rows empty, e.g. with `space(1)`.
+    pub fn frame_with_dividers(&mut self, divider_rows: &[usize], f: impl FnOnce(&mut Ui<T>)) {
+        let (start_x, start_y) = (self.cursor_x, self.cursor_y);
+        let (inner_x, inner_y) = (start_x + 1, start_y + 1);
+        let mut child = self.nested(inner_x, inner_y, LayoutKind::Vertical);
+        child.available_x = child.available_x.map(|w| w.saturating_sub(2));
+        child.available_y = child.available_y.map(|h| h.saturating_sub(2));
+        f(&mut child);
+        let inner_w = child.max_x - inner_x;
+        let last_divider = divider_rows.iter().map(|&row| row + 1).max();
+        let inner_h = (child.max_y - inner_y).max(last_divider.unwrap_or(0));
+        let (w, h) = (inner_w + 2, inner_h + 2);
+
+        if self.draw {
+            let [
+                top_left,
+                top_right,
+                bottom_left,
+                bottom_right,
+                double_h,
+                double_v,
+            ] = DOUBLE_BOX;
+            let [join_left, join_right, ..] = SINGLE_INTO_DOUBLE;
+            let (right, bottom) = (start_x + w - 1, start_y + h - 1);
+            for x in inner_x..right {
+                self.put_char(x, start_y, double_h);
+                self.put_char(x, bottom, double_h);
+            }
+            for y in inner_y..bottom {
+                self.put_char(start_x, y, double_v);
+                self.put_char(right, y, double_v);
+            }
+            self.put_char(start_x, start_y, top_left);
+            self.put_char(right, start_y, top_right);
+            self.put_char(start_x, bottom, bottom_left);
+            self.put_char(right, bottom, bottom_right);
+            for &row in divider_rows {
+                let y = inner_y + row;
+                for x in inner_x..right {
+                    self.put_char(x, y, '─');
+                }
+                self.put_char(start_x, y, join_left);
+                self.put_char(right, y, join_right);
+            }
+        }
+        self.advance(w, h);
+    }
+    /// Splits the available width proportionally to `ratios`, calling `f` once per region.
+    pub fn hsplit(&mut self, ratios: &[u16], f: impl Fn(usize, &mut Ui<T>)) {
+        let (bound_x, _) = self.bounds();
+        let total = self
+            .available_x
+            .unwrap_or(bound_x.saturating_sub(self.cursor_x));
+        let (start_x, start_y) = (self.cursor_x, self.cursor_y);
+        let mut x = start_x;
+        let mut used_h = 0;
+        for (i, w) in split_sizes(total, ratios).into_iter().enumerate() {
+            let mut region = self.nested(x, start_y, LayoutKind::Vertical);
+            region.available_x = Some(w);
+            f(i, &mut region);
+            used_h = used_h.max(region.max_y - start_y);
+            x += w;
+        }
+        self.advance(total, used_h);
+    }
+    /// Splits the available height proportionally to `ratios`, calling `f` once per region.
+    pub fn vsplit(&mut self, ratios: &[u16], f: impl Fn(usize, &mut Ui<T>)) {
+        let (_, bound_y) = self.bounds();
+        let total = self
+            .available_y
+            .unwrap_or(bound_y.saturating_sub(self.cursor_y));
+        let (start_x, start_y) = (self.cursor_x, self.cursor_y);
+        let mut y = start_y;
+        let mut used_w = 0;
+        for (i, h) in split_sizes(total, ratios).into_iter().enumerate() {
+            let mut region = self.nested(start_x, y, LayoutKind::Vertical);
+            region.available_y = Some(h);
+            f(i, &mut region);
+            used_w = used_w.max(region.max_x - start_x);
+            y += h;
+        }
+        self.advance(used_w, total);
+    }
+    /// First item flush-left, last flush-right, the rest evenly spaced in between.
+    pub fn horizontal_spread(&mut self, items: &[&str]) {
+        let (bound_x, _) = self.bounds();
+        let total = self
+            .available_x
+            .unwrap_or(bound_x.saturating_sub(self.cursor_x));
+        let lens: Vec<usize> = items.iter().map(|item| self.text_width(item)).collect();
+        let gaps = items.len().saturating_sub(1).max(1);
+        let free = total.saturating_sub(lens.iter().sum());
+        let mut x = self.cursor_x;
+        for (i, (item, len)) in items.iter().zip(&lens).enumerate() {
+            self.write_str(x, self.cursor_y, item);
+            x += len + free / gaps + usize::from(i < free % gaps);
+        }
+        self.advance(total, 1);
+    }
     pub fn label(&mut self, text: &str) {
         self.add(Label::from(text));
     }
+    pub fn label_vcenter(&mut self, text: &str, height: usize) {
+        let y = self.cursor_y + height.saturating_sub(1) / 2;
+        self.write_str(self.cursor_x, y, text);
+        self.advance(self.text_width(text), height);
+    }
+    /// `lines` numbered from `start` in a right-aligned gutter as wide as the largest
+    /// number. Content is cut off where the `width` wide block ends.
+    pub fn numbered_lines(&mut self, lines: &[&str], start: usize, width: usize) {
+        const SEP: &str = " │ ";
+        let last = start.saturating_add(lines.len().saturating_sub(1));
+        let gutter = last.to_string().len();
+        let content_x = gutter + self.text_width(SEP);
+        if self.draw {
+            for (i, line) in lines.iter().enumerate() {
+                let y = self.cursor_y + i;
+                for dx in 0..width {
+                    self.put_char(self.cursor_x + dx, y, ' ');
+                }
+                let prefix = format!("{:>gutter$}{SEP}", start.saturating_add(i));
+                let prefix = self.truncate_to_width(&prefix, width);
+                self.write_str(self.cursor_x, y, prefix);
+                let content = self.truncate_to_width(line, width.saturating_sub(content_x));
+                self.write_str(self.cursor_x + content_x, y, content);
+            }
+        }
+        self.advance(width, lines.len());
+    }
+    /// One row per pair. `Align::Left` lines values up in a column after the widest key
+    /// and a `: ` (as does `Align::Center`), `Align::Right` pushes them to the right edge
+    /// of the available width.
+    /// The gap after each key is filled with `leader`, e.g. `'.'` for `CPU ..... 42%`.
+    pub fn kv_list(&mut self, pairs: &[(&str, &str)], align: Align, leader: char) {
+        const SEP: &str = ": ";
+        let key_w = pairs
+            .iter()
+            .map(|(k, _)| self.text_width(k))
+            .max()
+            .unwrap_or(0);
+        let value_w = pairs
+            .iter()
+            .map(|(_, v)| self.text_width(v))
+            .max()
+            .unwrap_or(0);
+        let natural = if pairs.is_empty() {
+            0
+        } else {
+            key_w + SEP.len() + value_w
+        };
+        let w = match align {
+            Align::Left | Align::Center => natural,
+            Align::Right => self.available_x.unwrap_or(natural).max(natural),
+        };
+        if self.draw {
+            let x = self.cursor_x;
+            for (i, (key, value)) in pairs.iter().enumerate() {
+                let y = self.cursor_y + i;
+                for dx in 0..w {
+                    self.put_char(x + dx, y, ' ');
+                }
+                let key_len = self.text_width(key);
+                self.write_str(x, y, key);
+                match align {
+                    Align::Left | Align::Center => {
+                        for lx in x + key_len..x + key_w {
+                            self.put_char(lx, y, leader);
+                        }
+                        self.write_str(x + key_w, y, SEP);
+                        self.write_str(x + key_w + SEP.len(), y, value);
+                    }
+                    Align::Right => {
+                        // a space on either side of the leader
+                        let value_x = x + w - self.text_width(value);
+                        for lx in x + key_len + 1..value_x - 1 {
+                            self.put_char(lx, y, leader);
+                        }
+                        self.write_str(value_x, y, value);
+                    }
+                }
+            }
+        }
+        self.advance(w, pairs.len());
+    }
+    /// Word-wraps `text` to `width`. Each line is placed by `align`, except that
+    /// `Justify::Full` stretches all but the last line to the full width.
+    /// A width of 0 draws nothing and takes no space.
+    pub fn paragraph(&mut self, text: &str, width: usize, justify: Justify, align: Align) {
+        if width == 0 {
+            return;
+        }
+        let lines = wrap_words(text, width, |s| self.text_width(s));
+        if self.draw {
+            let last = lines.len().saturating_sub(1);
+            for (i, words) in lines.iter().enumerate() {
+                let line = match justify {
+                    Justify::Full if i < last => justify_line(words, width, |s| self.text_width(s)),
+                    _ => words.join(" "),
+                };
+                let y = self.cursor_y + i;
+                for dx in 0..width {
+                    self.put_char(self.cursor_x + dx, y, ' ');
+                }
+                let line = self.truncate_to_width(&line, width);
+                let slack = width - self.text_width(line);
+                let x = match align {
+                    Align::Left => self.cursor_x,
+                    Align::Center => self.cursor_x + slack / 2,
+                    Align::Right => self.cursor_x + slack,
+                };
+                self.write_str(x, y, line);
+            }
+        }
+        self.advance(width, lines.len());
+    }
+    /// `value` right-aligned in `width`; a width of 0 draws nothing and takes no space.
     pub fn number_i64(&mut self, value: i64, width: usize) {
+        if width == 0 {
+            return;
+        }
+        if self.visible_span(self.cursor_x, self.cursor_y, width) {
+            let y = self.cursor_y - self.scroll_y;
+            self.buf.write_i64_right(self.cursor_x, y, value, width);
+        }
+        self.advance(width, 1);
+    }
+    /// `value` with its sign (`+`, `-` or a space for zero) in the first of `width`
+    /// columns and the magnitude right-aligned in the rest, so digits of mixed-sign
+    /// values line up. High digits that don't fit are dropped, as in `number_i64`, and
+    /// a width of 0 draws nothing.
+    pub fn number_i64_signed(&mut self, value: i64, width: usize) {
+        if width == 0 {
+            return;
+        }
+        if self.draw {
+            let sign = match value.signum() {
+                1 => '+',
+                -1 => '-',
+                _ => ' ',
+            };
+            let digits = value.unsigned_abs().to_string();
+            let digits = &digits[digits.len().saturating_sub(width - 1)..];
+            let field = format!("{sign}{digits:>0$}", width - 1);
+            self.write_str(self.cursor_x, self.cursor_y, &field);
+        }
+        self.advance(width, 1);
+    }
+    /// `◄ value ►` with the value right-aligned in `width`; unfocused it shows `< value >`.
+    /// Returns the decrement and increment hit areas; the app owns and changes the value.
+    pub fn number_spin(&mut self, value: i64, width: usize, focused: bool) -> (Rect, Rect) {
+        let (x, y) = (self.cursor_x, self.cursor_y);
+        let (dec, inc) = if focused { ('◄', '►') } else { ('<', '>') };
+        let total = width + 4;
         if self.draw {
-            self.buf
-                .write_i64_right(self.cursor_x, self.cursor_y, value, width);
+            self.put_char(x, y, dec);
+            self.put_char(x + 1, y, ' ');
+            if self.visible_span(x + 2, y, width) {
+                let sy = y - self.scroll_y;
+                self.buf.write_i64_right(x + 2, sy, value, width);
+            }
+            self.put_char(x + width + 2, y, ' ');
+            self.put_char(x + width + 3, y, inc);
+        }
+        let rect = Rect::new(x, y, total, 1);
+        self.record_a11y(rect, A11yRole::SpinButton, &value.to_string());
+        self.advance(total, 1);
+        (Rect::new(x, y, 1, 1), Rect::new(x + width + 3, y, 1, 1))
+    }
+    /// `value` with `precision` decimals in `width`, too long a value shown as `overflow`
+    /// says. A width of 0 draws nothing and takes no space.
+    pub fn number_f64(&mut self, value: f64, precision: usize, width: usize, overflow: Overflow) {
+        if width == 0 {
+            return;
+        }
+        if self.visible_span(self.cursor_x, self.cursor_y, width) {
+            let text = format!("{:.*}", precision, value);
+            let (x, y) = (self.cursor_x, self.cursor_y - self.scroll_y);
+            match overflow {
+                Overflow::Hash if text.len() > width => {
+                    for i in 0..width {
+                        self.buf.put_char(x + i, y, '#');
+                    }
+                }
+                Overflow::Ellipsis if text.len() > width => {
+                    self.buf.write_str(x, y, &text[..width - 1]);
+                    self.buf.put_char(x + width - 1, y, '…');
+                }
+                _ => self.buf.write_f64_right(x, y, value, width, precision),
+            }
         }
         self.advance(width, 1);
     }
-    pub fn number_f64(&mut self, value: f64, precision: usize, width: usize) {
+    /// One value per row with the decimal points lined up: the integer part is
+    /// right-aligned in `int_width`, the fraction left-aligned in `frac_width`.
+    /// A value whose integer part doesn't fit shows as `#`s.
+    pub fn number_f64_column(
+        &mut self,
+        values: &[f64],
+        precision: usize,
+        int_width: usize,
+        frac_width: usize,
+    ) {
+        let width = int_width + 1 + frac_width;
+        // digits past `frac_width` are rounded away, not cut off
+        let precision = precision.min(frac_width);
+        if self.draw {
+            for (i, value) in values.iter().enumerate() {
+                let text = format!("{:.*}", precision, value);
+                let (int, frac) = text.split_once('.').unwrap_or((&text, ""));
+                let field = if int.len() > int_width {
+                    "#".repeat(width)
+                } else {
+                    let dot = if precision > 0 { '.' } else { ' ' };
+                    format!("{int:>int_width$}{dot}{frac:<frac_width$}")
+                };
+                self.write_str(self.cursor_x, self.cursor_y + i, &field);
+            }
+        }
+        self.advance(width, values.len());
+    }
+    pub fn linechart(&mut self, series: &[f64], width: usize, height: usize) {
+        if self.draw && width > 0 && height > 0 {
+            let mut canvas = BrailleCanvas::new(width, height);
+            let (min, max) = series
+                .iter()
+                .filter(|v| v.is_finite())
+                .fold((f64::INFINITY, f64::NEG_INFINITY), |(lo, hi), &v| {
+                    (lo.min(v), hi.max(v))
+                });
+            let pw = canvas.pixel_width() - 1;
+            let ph = canvas.pixel_height() - 1;
+            let to_px = |i: usize| {
+                if series.len() > 1 {
+                    i * pw / (series.len() - 1)
+                } else {
+                    0
+                }
+            };
+            let to_py = |v: f64| scale_inverted(v, min, max, ph);
+            let mut prev: Option<(usize, usize)> = None;
+            for (i, &v) in series.iter().enumerate() {
+                if !v.is_finite() {
+                    prev = None;
+                    continue;
+                }
+                let point = (to_px(i), to_py(v));
+                match prev {
+                    Some((x0, y0)) => canvas.line(x0, y0, point.0, point.1),
+                    None => canvas.set(point.0, point.1),
+                }
+                prev = Some(point);
+            }
+            self.put_canvas(&canvas);
+        }
+        self.advance(width, height);
+    }
+    pub fn scatter(&mut self, points: &[(f64, f64)], width: usize, height: usize) {
+        if self.draw && width > 0 && height > 0 {
+            let mut canvas = BrailleCanvas::new(width, height);
+            let valid = || points.iter().filter(|(x, y)| !x.is_nan() && !y.is_nan());
+            let (min_x, max_x, min_y, max_y) = valid().fold(
+                (
+                    f64::INFINITY,
+                    f64::NEG_INFINITY,
+                    f64::INFINITY,
+                    f64::NEG_INFINITY,
+                ),
+                |(lx, hx, ly, hy), &(x, y)| (lx.min(x), hx.max(x), ly.min(y), hy.max(y)),
+            );
+            let pw = canvas.pixel_width() - 1;
+            let ph = canvas.pixel_height() - 1;
+            for &(x, y) in valid() {
+                canvas.set(
+                    scale(x, min_x, max_x, pw),
+                    scale_inverted(y, min_y, max_y, ph),
+                );
+            }
+            self.put_canvas(&canvas);
+        }
+        self.advance(width, height);
+    }
+    /// Picks the largest precision that fits `width`, falling back to scientific notation.
+    /// A width of 0 draws nothing and takes no space.
+    pub fn number_auto(&mut self, value: f64, width: usize) {
+        if width == 0 {
+            return;
+        }
         if self.draw {
-            self.buf
-                .write_f64_right(self.cursor_x, self.cursor_y, value, width, precision);
+            let text = format_auto(value, width);
+            for i in 0..width {
+                self.put_char(self.cursor_x + i, self.cursor_y, ' ');
+            }
+            let len = self.text_width(&text);
+            self.write_str(
+                self.cursor_x + width.saturating_sub(len),
+                self.cursor_y,
+                &text,
+            );
         }
         self.advance(width, 1);
     }
 }
-trait Layout {
+// dot bit for each sub-pixel of a braille cell, indexed by [py % 4][px % 2]
+const BRAILLE_DOTS: [[u8; 2]; 4] = [[0x01, 0x08], [0x02, 0x10], [0x04, 0x20], [0x40, 0x80]];
+pub struct BrailleCanvas {
+    width: usize,
+    height: usize,
+    dots: Vec<u8>,
+}
+impl BrailleCanvas {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            dots: vec![0; width * height],
+        }
+    }
+    pub fn pixel_width(&self) -> usize {
+        self.width * 2
+    }
+    pub fn pixel_height(&self) -> usize {
+        self.height * 4
+    }
+    pub fn set(&mut self, px: usize, py: usize) {
+        if px >= self.pixel_width() || py >= self.pixel_height() {
+            return;
+        }
+        self.dots[(py / 4) * self.width + px / 2] |= BRAILLE_DOTS[py % 4][px % 2];
+    }
+    pub fn line(&mut self, x0: usize, y0: usize, x1: usize, y1: usize) {
+        bresenham(x0, y0, x1, y1, |x, y| self.set(x, y));
+    }
+    fn glyph(&self, cx: usize, cy: usize) -> char {
+        match self.dots[cy * self.width + cx] {
+            0 => ' ',
+            bits => char::from_u32(0x2800 + bits as u32).unwrap_or(' '),
+        }
+    }
+    pub fn render<T: DrawTarget>(&self, buf: &mut T, x: usize, y: usize) {
+        for cy in 0..self.height {
+            for cx in 0..self.width {
+                buf.put_char(x + cx, y + cy, self.glyph(cx, cy));
+            }
+        }
+    }
+}
+/// Frame rate over the last `window` frames, fed a timestamp per flush.
+pub struct FpsCounter {
+    frames: VecDeque<Instant>,
+    window: usize,
+}
+impl FpsCounter {
+    pub fn new(window: usize) -> Self {
+        Self {
+            frames: VecDeque::with_capacity(window),
+            window: window.max(2),
+        }
+    }
+    /// Records a frame flushed now.
+    pub fn tick(&mut self) {
+        self.tick_at(Instant::now());
+    }
+    pub fn tick_at(&mut self, at: Instant) {
+        if self.frames.len() == self.window {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(at);
+    }
+    /// Frames per second across the recorded frames, 0 until two are in.
+    pub fn fps(&self) -> f64 {
+        let (Some(first), Some(last)) = (self.frames.front(), self.frames.back()) else {
+            return 0.0;
+        };
+        let elapsed = last.saturating_duration_since(*first).as_secs_f64();
+        if elapsed == 0.0 {
+            return 0.0;
+        }
+        (self.frames.len() - 1) as f64 / elapsed
+    }
+}
+pub struct LogView {
+    lines: VecDeque<String>,
+    capacity: usize,
+    scroll: usize,
+    hscroll: usize,
+}
+impl LogView {
+    pub fn new(capacity: usize) -> Self {
+        Self {
+            lines: VecDeque::with_capacity(capacity),
+            capacity,
+            scroll: 0,
+            hscroll: 0,
+        }
+    }
+    pub fn push(&mut self, line: impl Into<String>) {
+        if self.capacity == 0 {
+            return;
+        }
+        if self.lines.len() == self.capacity {
+            self.lines.pop_front();
+        }
+        self.lines.push_back(line.into());
+    }
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+    // scrolling counts lines up from the bottom
+    pub fn scroll_up(&mut self, lines: usize) {
+        self.scroll = (self.scroll + lines).min(self.lines.len());
+    }
+    pub fn scroll_down(&mut self, lines: usize) {
+        self.scroll = self.scroll.saturating_sub(lines);
+    }
+    pub fn set_hscroll(&mut self, column: usize) {
+        self.hscroll = column;
+    }
+    pub fn render<T: DrawTarget>(&self, ui: &mut Ui<T>, width: usize, height: usize, follow: bool) {
+        if ui.draw {
+            let end = if follow {
+                self.lines.len()
+            } else {
+                self.lines.len() - self.scroll.min(self.lines.len())
+            };
+            let start = end.saturating_sub(height);
+            for dy in 0..height {
+                let y = ui.cursor_y + dy;
+                for dx in 0..width {
+                    ui.put_char(ui.cursor_x + dx, y, ' ');
+                }
+                if let Some(line) = self.lines.get(start + dy).filter(|_| start + dy < end) {
+                    let rest: String = line.chars().skip(self.hscroll).collect();
+                    let visible = ui.truncate_to_width(&rest, width);
+                    ui.write_str(ui.cursor_x, y, visible);
+                }
+            }
+        }
+        ui.advance(width, height);
+    }
+}
+/// A key press, as far as the widgets taking input need it.
+#[derive(Copy, Clone, PartialEq, Eq, Debug)]
+pub enum Key {
+    Char(char),
+    Backspace,
+    Up,
+    Down,
+    Enter,
+}
+/// A filterable list of `(name, id)` commands with a query line and a selection.
+pub struct CommandPalette {
+    commands: Vec<(String, usize)>,
+    query: String,
+    selected: usize,
+    // (command index, matched char indices), best first; redone when the query changes
+    ranked: Vec<(usize, Vec<usize>)>,
+}
+impl CommandPalette {
+    pub fn new(commands: Vec<(String, usize)>) -> Self {
+        let mut palette = Self {
+            commands,
+            query: String::new(),
+            selected: 0,
+            ranked: Vec::new(),
+        };
+        palette.rank();
+        palette
+    }
+    pub fn query(&self) -> &str {
+        &self.query
+    }
+    pub fn push_char(&mut self, ch: char) {
+        self.query.push(ch);
+        self.rank();
+    }
+    pub fn backspace(&mut self) {
+        self.query.pop();
+        self.rank();
+    }
+    pub fn select_next(&mut self) {
+        self.selected = (self.selected + 1).min(self.ranked.len().saturating_sub(1));
+    }
+    pub fn select_prev(&mut self) {
+        self.selected = self.selected.saturating_sub(1);
+    }
+    /// Typing edits the query, Up/Down move the selection. Returns the selected
+    /// command's id on Enter.
+    pub fn handle_key(&mut self, key: Key) -> Option<usize> {
+        match key {
+            Key::Char(ch) => self.push_char(ch),
+            Key::Backspace => self.backspace(),
+            Key::Up => self.select_prev(),
+            Key::Down => self.select_next(),
+            Key::Enter => return self.selected(),
+        }
+        None
+    }
+    fn rank(&mut self) {
+        let mut ranked: Vec<_> = self
+            .commands
+            .iter()
+            .enumerate()
+            .filter_map(|(i, (name, _))| {
+                text::fuzzy_score(&self.query, name).map(|(score, indices)| (score, i, indices))
+            })
+            .collect();
+        ranked.sort_by_key(|&(score, ..)| std::cmp::Reverse(score));
+        self.ranked = ranked
+            .into_iter()
+            .map(|(_, i, indices)| (i, indices))
+            .collect();
+        self.selected = 0;
+    }
+    /// Commands matching the query, best `text::fuzzy_score` first; ties keep their order.
+    pub fn matches(&self) -> Vec<(&str, usize)> {
+        self.ranked
+            .iter()
+            .map(|&(i, _)| (self.commands[i].0.as_str(), self.commands[i].1))
+            .collect()
+    }
+    /// Id of the selected command, if anything matches.
+    pub fn selected(&self) -> Option<usize> {
+        self.ranked
+            .get(self.selected)
+            .map(|&(i, _)| self.commands[i].1)
+    }
+    /// The query line, then the matches with the query's chars in yellow.
+    pub fn render<T: DrawTarget>(&self, ui: &mut Ui<T>, width: usize, height: usize) {
+        if ui.draw && height > 0 {
+            // the selected row stays in view
+            let rows = height - 1;
+            let first = (self.selected + 1).saturating_sub(rows);
+            for dy in 0..height {
+                let y = ui.cursor_y + dy;
+                for dx in 0..width {
+                    ui.put_char(ui.cursor_x + dx, y, ' ');
+                }
+                if dy == 0 {
+                    let line = format!("> {}", self.query);
+                    ui.write_str(ui.cursor_x, y, ui.truncate_to_width(&line, width));
+                    continue;
+                }
+                let Some((command, indices)) = self.ranked.get(first + dy - 1) else {
+                    continue;
+                };
+                let name = &self.commands[*command].0;
+                let marker = if first + dy - 1 == self.selected {
+                    '>'
+                } else {
+                    ' '
+                };
+                let line = format!("{marker} {name}");
+                ui.write_str(ui.cursor_x, y, ui.truncate_to_width(&line, width));
+                let mut x = 2;
+                for (i, ch) in name.chars().enumerate() {
+                    let w = ui.buf.char_width(ch);
+                    if x + w > width {
+                        break;
+                    }
+                    if indices.contains(&i) {
+                        ui.put_char_fg(ui.cursor_x + x, y, ch, Color::Yellow);
+                    }
+                    x += w;
+                }
+            }
+        }
+        ui.advance(width, height);
+    }
+}
+// divides `total` by weight, the rounding remainder goes to the first regions
+fn split_sizes(total: usize, ratios: &[u16]) -> Vec<usize> {
+    let sum: usize = ratios.iter().map(|&r| r as usize).sum();
+    if sum == 0 {
+        return vec![0; ratios.len()];
+    }
+    let mut sizes: Vec<usize> = ratios.iter().map(|&r| total * r as usize / sum).collect();
+    let mut remainder = total - sizes.iter().sum::<usize>();
+    for (size, &r) in sizes.iter_mut().zip(ratios) {
+        if remainder == 0 {
+            break;
+        }
+        if r > 0 {
+            *size += 1;
+            remainder -= 1;
+        }
+    }
+    sizes
+}
+// greedy word wrap, a word longer than `width` gets a line of its own; words are
+// measured by `text_width` so the target's width overrides apply
+fn wrap_words(text: &str, width: usize, text_width: impl Fn(&str) -> usize) -> Vec<Vec<&str>> {
+    let mut lines: Vec<Vec<&str>> = Vec::new();
+    let mut line_len = 0;
+    for word in text.split_whitespace() {
+        let len = text_width(word);
+        match lines.last_mut() {
+            Some(line) if line_len + 1 + len <= width => {
+                line.push(word);
+                line_len += 1 + len;
+            }
+            _ => {
+                lines.push(vec![word]);
+                line_len = len;
+            }
+        }
+    }
+    lines
+}
+// spreads the spaces so the line fills `width`, left gaps get the remainder first
+fn justify_line(words: &[&str], width: usize, text_width: impl Fn(&str) -> usize) -> String {
+    if words.len() < 2 {
+        return words.join(" ");
+    }
+    let gaps = words.len() - 1;
+    let text_len: usize = words.iter().map(|w| text_width(w)).sum();
+    let spaces = width.saturating_sub(text_len).max(gaps);
+    let mut line = String::with_capacity(width);
+    for (i, word) in words.iter().enumerate() {
+        line.push_str(word);
+        if i < gaps {
+            let n = spaces / gaps + usize::from(i < spaces % gaps);
+            line.extend(std::iter::repeat_n(' ', n));
+        }
+    }
+    line
+}
+// maps `v` within `min..=max` onto `0..=span`
+fn scale(v: f64, min: f64, max: f64, span: usize) -> usize {
+    if max > min {
+        ((v - min) / (max - min) * span as f64).round() as usize
+    } else {
+        span / 2
+    }
+}
+// like `scale`, but with `max` at 0 as rows grow downwards
+fn scale_inverted(v: f64, min: f64, max: f64, span: usize) -> usize {
+    span - scale(v, min, max, span)
+}
+fn format_auto(value: f64, width: usize) -> String {
+    if !value.is_finite() {
+        let text = value.to_string();
+        return if text.len() <= width {
+            text
+        } else {
+            "#".repeat(width)
+        };
+    }
+    for precision in (0..width).rev() {
+        let text = format!("{:.*}", precision, value);
+        if text.len() <= width {
+            return text;
+        }
+    }
+    for precision in (0..width).rev() {
+        let text = format!("{:.*e}", precision, value);
+        if text.len() <= width {
+            return text;
+        }
+    }
+    "#".repeat(width)
+}
+pub trait Widget {
     fn width(&self) -> usize;
     fn height(&self) -> usize;
-    fn init_position_from_other(&mut self, x: usize, y: usize);
-    fn init_position_other_layout<L: Layout>(&self, layout: &mut L);
-    fn update_self_size_from_other<L: Layout>(&mut self, layout: &L);
-}
-struct VLayout {
-    x: usize,
-    y: usize,
-    gap: usize,
-    current_y: usize,
-    width: usize,
+    fn render(&self, buf: &mut ScreenBuffer, x: usize, y: usize);
 }
-impl VLayout {
-    fn new(x: usize, y: usize, gap: usize) -> Self {
-        Self {
-            x,
-            y,
-            gap,
-            current_y: y,
-            width: 0,
-        }
-    }
-    fn write_str(&mut self, buf: &mut ScreenBuffer, text: &str) {
-        let widget = TextWidget::from(text);
-        self.widget(buf, &widget);
-    }
-    fn widget<W: Widget>(&mut self, buf: &mut ScreenBuffer, widget: &W) {
-        widget.render(buf, self.x, self.current_y);
-        self.width = self.width.max(widget.width());
-        self.current_y += widget.height() + self.gap;
-    }
+
+/// A single line of text, the leaf of a `CompositeWidget`.
+pub struct TextWidget<'a> {
+    text: &'a str,
 }
-impl Layout for VLayout {
+impl<'a> Widget for TextWidget<'a> {
     fn width(&self) -> usize {
-        self.width
+        self.text.len()
     }
 
     fn height(&self) -> usize {
-        (self.current_y - self.y).saturating_sub(self.gap).max(0)
+        1
     }
 
-    fn init_position_from_other(&mut self, x: usize, y: usize) {
-        self.x = x;
-        self.y = y;
-        self.current_y = y;
-    }
-    fn init_position_other_layout<L: Layout>(&self, layout: &mut L) {
-        layout.init_position_from_other(self.x, self.current_y);
+    fn render(&self, buf: &mut ScreenBuffer, x: usize, y: usize) {
+        buf.write_str(x, y, self.text);
     }
-
-    fn update_self_size_from_other<L: Layout>(&mut self, layout: &L) {
-        self.current_y += layout.height() + self.gap;
-        self.width = self.width.max(layout.width());
+}
+impl<'a> From<&'a str> for TextWidget<'a> {
+    fn from(value: &'a str) -> TextWidget<'a> {
+        Self { text: value }
     }
 }
-struct HLayout {
-    x: usize,
-    y: usize,
+// stacks child widgets so reusable components don't need their own layout arithmetic
+pub struct CompositeWidget<'a> {
+    children: Vec<Box<dyn Widget + 'a>>,
+    layout: LayoutKind,
     gap: usize,
-    current_x: usize,
-    height: usize,
 }
-impl HLayout {
-    fn new(x: usize, y: usize, gap: usize) -> Self {
+impl<'a> CompositeWidget<'a> {
+    pub fn vertical(gap: usize) -> Self {
+        Self {
+            children: Vec::new(),
+            layout: LayoutKind::Vertical,
+            gap,
+        }
+    }
+    pub fn horizontal(gap: usize) -> Self {
         Self {
-            x,
-            y,
+            children: Vec::new(),
+            layout: LayoutKind::Horizontal,
             gap,
-            current_x: x,
-            height: 0,
         }
     }
-    fn write_str(&mut self, buf: &mut ScreenBuffer, text: &str) {
-        let widget = TextWidget::from(text);
-        self.widget(buf, &widget);
+    pub fn with<W: Widget + 'a>(mut self, widget: W) -> Self {
+        self.children.push(Box::new(widget));
+        self
     }
-    fn widget<W: Widget>(&mut self, buf: &mut ScreenBuffer, widget: &W) {
-        widget.render(buf, self.current_x, self.y);
-        self.height = self.height.max(widget.height());
-        self.current_x += widget.width() + self.gap;
+    fn gaps(&self) -> usize {
+        self.gap * self.children.len().saturating_sub(1)
     }
 }
-impl Layout for HLayout {
+impl<'a> Widget for CompositeWidget<'a> {
     fn width(&self) -> usize {
-        (self.current_x - self.x).saturating_sub(self.gap).max(0)
+        let widths = self.children.iter().map(|c| c.width());
+        match self.layout {
+            LayoutKind::Vertical => widths.max().unwrap_or(0),
+            LayoutKind::Horizontal => widths.sum::<usize>() + self.gaps(),
+        }
     }
 
     fn height(&self) -> usize {
-        self.height
+        let heights = self.children.iter().map(|c| c.height());
+        match self.layout {
+            LayoutKind::Vertical => heights.sum::<usize>() + self.gaps(),
+            LayoutKind::Horizontal => heights.max().unwrap_or(0),
+        }
     }
 
-    fn init_position_from_other(&mut self, x: usize, y: usize) {
-        self.current_x = x;
-        self.x = x;
-        self.y = y;
+    fn render(&self, buf: &mut ScreenBuffer, x: usize, y: usize) {
+        let (mut cx, mut cy) = (x, y);
+        for child in &self.children {
+            child.render(buf, cx, cy);
+            match self.layout {
+                LayoutKind::Vertical => cy += child.height() + self.gap,
+                LayoutKind::Horizontal => cx += child.width() + self.gap,
+            }
+        }
     }
-    fn init_position_other_layout<L: Layout>(&self, layout: &mut L) {
-        layout.init_position_from_other(self.current_x, self.y);
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    fn row(buf: &ScreenBuffer, y: usize) -> String {
+        (0..buf.width)
+            .map(|x| buf.cells[buf.index(x, y)].ch)
+            .collect()
     }
-
-    fn update_self_size_from_other<L: Layout>(&mut self, layout: &L) {
-        self.current_x += layout.width() + self.gap;
-        self.height = self.height.max(layout.height());
+    #[test]
+    fn render_test_fits_buffer() {
+        let mut buf = ScreenBuffer::new(120, 20);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        render_test(&mut ui);
+        assert_eq!(row(&buf, 0).trim_end().len(), 70);
     }
-}
-struct GridLayout {
-    x: usize,
-    y: usize,
-    cols: usize,
-    gap_x: usize,
-    gap_y: usize,
-
-    current_col: usize,
-    current_row: usize,
+    #[test]
+    fn resolve_lengths() {
+        assert_eq!(resolve(Length::Chars(5), Some(3)), 5);
+        assert_eq!(resolve(Length::Chars(5), None), 5);
+        assert_eq!(resolve(Length::Percent(50), Some(9)), 4);
+        assert_eq!(resolve(Length::Percent(150), Some(10)), 10);
+        assert_eq!(resolve(Length::Percent(50), None), 0);
+        assert_eq!(resolve(Length::Fill, Some(7)), 7);
+        assert_eq!(resolve(Length::Fill, None), 0);
+        assert_eq!(resolve(Length::Auto, Some(7)), 0);
+    }
+    #[test]
+    fn label_percent_length() {
+        let mut buf = ScreenBuffer::new(20, 1);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.with_available(Some(20), None, |ui| {
+            ui.horizontal(|ui| {
+                ui.add(Label::from("ab").with_length(Length::Percent(25)));
+                ui.label("|");
+            });
+        });
+        assert_eq!(row(&buf, 0).trim_end(), "ab   |");
+    }
+    #[test]
+    fn char_width_override() {
+        let mut buf = ScreenBuffer::new(5, 1);
+        buf.write_str(0, 0, "a→b");
+        assert_eq!(row(&buf, 0), "a→b  ");
+        buf.set_char_width('→', 2);
+        buf.write_str(0, 0, "a→b");
+        assert_eq!(row(&buf, 0), "a→\0b ");
+        assert_eq!(buf.to_string(), "a→b \n");
+    }
+    #[test]
+    fn layout_uses_char_width_override() {
+        let mut buf = ScreenBuffer::new(6, 3);
+        buf.set_char_width('→', 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.horizontal(|ui| {
+            ui.label("a→b");
+            ui.label("c");
+        });
+        ui.add(Label::from("a→b").with_width(3));
+        ui.add(Label::from("→x").direction(Direction::Rtl));
+        assert_eq!(row(&buf, 0), "a→\0bc ");
+        assert_eq!(row(&buf, 1), "a→\0   ");
+        assert_eq!(row(&buf, 2), "x→    ");
+    }
+    #[test]
+    fn widgets_measure_with_char_width_override() {
+        let mut buf = ScreenBuffer::new(8, 6);
+        buf.set_char_width('→', 2);
+        let mut log = LogView::new(1);
+        log.push("a→bc");
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.horizontal_spread(&["→", "b"]);
+        ui.kv_list(&[("→", "1"), ("ab", "2")], Align::Left, '.');
+        ui.paragraph("→ a", 3, Justify::Left, Align::Right);
+        log.render(&mut ui, 3, 1, true);
+        assert_eq!(row(&buf, 0), "→\0     b");
+        assert_eq!(row(&buf, 1), "→\0: 1   ");
+        assert_eq!(row(&buf, 2), "ab: 2   ");
+        assert_eq!(row(&buf, 3), " →\0     ");
+        assert_eq!(row(&buf, 4), "  a     ");
+        assert_eq!(row(&buf, 5), "a→\0     ");
+    }
+    #[test]
+    fn content_bounds_of_label() {
+        let mut buf = ScreenBuffer::new(12, 6);
+        assert_eq!(buf.content_bounds(), None);
+        let mut ui = Ui::new(&mut buf, 5, 2);
+        ui.label("hi");
+        ui.label("you");
+        assert_eq!(buf.content_bounds(), Some(Rect::new(5, 2, 3, 2)));
+    }
+    #[test]
+    fn crop_region() {
+        let mut buf = ScreenBuffer::new(6, 4);
+        buf.write_str(0, 1, "abcdef");
+        buf.write_str(0, 2, "ghijkl");
+        assert_eq!(buf.crop(Rect::new(2, 1, 3, 2)).to_string(), "cde\nijk\n");
+        // clipped to the buffer
+        assert_eq!(buf.crop(Rect::new(4, 2, 9, 9)).to_string(), "kl\n  \n");
+    }
+    #[test]
+    fn palette_filters_and_ranks() {
+        let mut palette = CommandPalette::new(vec![
+            ("Save file".to_string(), 1),
+            ("Open file".to_string(), 2),
+            ("Find in files".to_string(), 3),
+            ("Quit".to_string(), 4),
+        ]);
+        for ch in "fi".chars() {
+            palette.push_char(ch);
+        }
+        let names: Vec<_> = palette.matches().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, ["Save file", "Open file", "Find in files"]);
+        palette.push_char('n');
+        let names: Vec<_> = palette.matches().into_iter().map(|(n, _)| n).collect();
+        assert_eq!(names, ["Find in files"]);
+        palette.backspace();
+        palette.select_next();
+        assert_eq!(palette.selected(), Some(2));
+        palette.backspace();
+        palette.backspace();
+        palette.push_char('q');
+        assert_eq!(palette.selected(), Some(4));
 
-    col_widths: Vec<usize>,
-    row_heights: Vec<usize>,
-}
-impl GridLayout {
-    fn new(x: usize, y: usize, cols: usize, gap_x: usize, gap_y: usize) -> Self {
-        Self {
-            x,
-            y,
-            cols,
-            gap_x,
-            gap_y,
-            current_col: 0,
-            current_row: 0,
-            col_widths: vec![0; cols],
-            row_heights: Vec::new(),
+        let mut buf = ScreenBuffer::new(10, 3);
+        palette.render(&mut Ui::new(&mut buf, 0, 0), 10, 3);
+        assert_eq!(row(&buf, 0), "> q       ");
+        assert_eq!(row(&buf, 1), "> Quit    ");
+        assert_eq!(row(&buf, 2), "          ");
+        assert_eq!(buf.cells[buf.index(2, 1)].fg(), Some(Color::Yellow));
+        assert_eq!(buf.cells[buf.index(3, 1)].fg(), None);
+    }
+    #[test]
+    fn palette_handles_keys() {
+        let mut palette = CommandPalette::new(vec![
+            ("Save".to_string(), 1),
+            ("Open".to_string(), 2),
+            ("Close".to_string(), 3),
+        ]);
+        assert_eq!(palette.handle_key(Key::Char('e')), None);
+        assert_eq!(palette.query(), "e");
+        palette.handle_key(Key::Down);
+        palette.handle_key(Key::Down);
+        assert_eq!(palette.handle_key(Key::Enter), Some(3));
+        palette.handle_key(Key::Up);
+        assert_eq!(palette.handle_key(Key::Enter), Some(2));
+        palette.handle_key(Key::Backspace);
+        assert_eq!((palette.query(), palette.selected()), ("", Some(1)));
+    }
+    #[test]
+    fn label_wrap_modes() {
+        let mut buf = ScreenBuffer::new(8, 5);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        let label = || Label::from("one two three").with_width(7);
+        ui.add(label().wrap(WrapMode::Truncate));
+        ui.add(label().wrap(WrapMode::Ellipsis));
+        ui.add(label().wrap(WrapMode::Wrap));
+        ui.label("end");
+        assert_eq!(row(&buf, 0), "one two ");
+        assert_eq!(row(&buf, 1), "one tw… ");
+        assert_eq!(row(&buf, 2), "one two ");
+        assert_eq!(row(&buf, 3), "three   ");
+        assert_eq!(row(&buf, 4), "end     ");
+    }
+    #[test]
+    fn terminal_title_osc() {
+        let mut out = Vec::new();
+        set_terminal_title(&mut out, "build: ok\x07").unwrap();
+        assert_eq!(out, b"\x1B]0;build: ok\x07");
+    }
+    // forwards to a ScreenBuffer and counts batches
+    struct BatchCounter {
+        inner: ScreenBuffer,
+        begins: usize,
+        ends: usize,
+    }
+    impl DrawTarget for BatchCounter {
+        fn clear(&mut self) {
+            self.inner.clear()
+        }
+        fn put_char(&mut self, x: usize, y: usize, ch: char) {
+            self.inner.put_char(x, y, ch)
+        }
+        fn flush(&self) {}
+        fn dimensions(&self) -> (usize, usize) {
+            self.inner.dimensions()
+        }
+        fn begin_batch(&mut self) {
+            self.begins += 1;
+        }
+        fn end_batch(&mut self) {
+            self.ends += 1;
         }
     }
+    #[test]
+    fn one_batch_per_frame() {
+        let mut target = BatchCounter {
+            inner: ScreenBuffer::new(10, 6),
+            begins: 0,
+            ends: 0,
+        };
+        let mut ui = Ui::new(&mut target, 0, 0);
+        ui.label("no frame widget");
+        ui.flush();
+        ui.clear();
+        ui.frame(1, BorderKind::Full, StretchHint::Compact, (0, 0), |ui| {
+            ui.frame(1, BorderKind::Full, StretchHint::Compact, (0, 0), |ui| {
+                ui.label("x")
+            });
+        });
+        ui.label("outside");
+        ui.flush();
+        ui.flush();
+        assert_eq!((target.begins, target.ends), (2, 2));
+    }
+    #[test]
+    fn reserve_advances_both_axes() {
+        let mut buf = ScreenBuffer::new(10, 10);
+        buf.write_str(0, 0, "keep");
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.reserve(4, 3);
+        assert_eq!((ui.max_x, ui.max_y, ui.cursor_y), (4, 3, 3));
+        ui.horizontal(|ui| {
+            ui.reserve(2, 5);
+            assert_eq!((ui.max_x, ui.max_y, ui.cursor_x), (2, 8, 2));
+        });
+        assert_eq!(row(&buf, 0), "keep      ");
+    }
+    #[test]
+    fn a11y_capture_records_labels() {
+        let mut buf = ScreenBuffer::new(10, 4);
+        let mut log = Vec::new();
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.label("not seen");
+        ui.capture_a11y(&mut log);
+        ui.horizontal(|ui| {
+            ui.label("ok");
+            ui.add(Label::from("cancel").with_width(7));
+        });
+        ui.number_spin(-3, 2, true);
+        ui.measure(|ui| ui.label("probe"));
+        let rects: Vec<_> = log
+            .iter()
+            .map(|n| (n.rect, n.role, n.text.as_str()))
+            .collect();
+        assert_eq!(
+            rects,
+            [
+                (Rect::new(0, 1, 2, 1), A11yRole::Label, "ok"),
+                (Rect::new(2, 1, 7, 1), A11yRole::Label, "cancel"),
+                (Rect::new(0, 2, 6, 1), A11yRole::SpinButton, "-3"),
+            ]
+        );
+    }
+    #[test]
+    fn signed_numbers_align_digits() {
+        let mut buf = ScreenBuffer::new(5, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.number_i64_signed(42, 4);
+        ui.number_i64_signed(-7, 4);
+        ui.number_i64_signed(0, 4);
+        ui.number_i64_signed(-12345, 4);
+        assert_eq!((ui.max_x, ui.max_y), (4, 4));
+        assert_eq!(row(&buf, 0), "+ 42 ");
+        assert_eq!(row(&buf, 1), "-  7 ");
+        assert_eq!(row(&buf, 2), "   0 ");
+        assert_eq!(row(&buf, 3), "-345 ");
+    }
+    // runs `f` on an empty 6x3 buffer, returning the Ui's extent and what was drawn
+    fn zero_width_run(f: impl FnOnce(&mut Ui<ScreenBuffer>)) -> ((usize, usize, usize), String) {
+        let mut buf = ScreenBuffer::new(6, 3);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        f(&mut ui);
+        let extent = (ui.max_x, ui.max_y, ui.cursor_y);
+        (extent, buf.to_string())
+    }
+    const BLANK_6X3: &str = "      \n      \n      \n";
+    #[test]
+    fn zero_width_label() {
+        // a label's width of 0 falls back to the text width
+        let (extent, text) = zero_width_run(|ui| ui.add(Label::from("abc").with_width(0)));
+        assert_eq!(extent, (3, 1, 1));
+        assert_eq!(text, "abc   \n      \n      \n");
+    }
+    #[test]
+    fn zero_width_number_i64() {
+        let (extent, text) = zero_width_run(|ui| {
+            ui.number_i64(-42, 0);
+            ui.number_i64_signed(-42, 0);
+        });
+        assert_eq!((extent, text.as_str()), ((0, 0, 0), BLANK_6X3));
+    }
+    #[test]
+    fn zero_width_number_f64() {
+        let (extent, text) = zero_width_run(|ui| {
+            ui.number_f64(2.5, 2, 0, Overflow::Ellipsis);
+            ui.number_f64(2.5, 2, 0, Overflow::Hash);
+            ui.number_auto(12345.0, 0);
+        });
+        assert_eq!((extent, text.as_str()), ((0, 0, 0), BLANK_6X3));
+    }
+    #[test]
+    fn zero_width_paragraph() {
+        let (extent, text) =
+            zero_width_run(|ui| ui.paragraph("some words", 0, Justify::Left, Align::Left));
+        assert_eq!((extent, text.as_str()), ((0, 0, 0), BLANK_6X3));
+    }
+    #[test]
+    fn kv_list_aligns_values() {
+        let mut buf = ScreenBuffer::new(16, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        let pairs = [("host", "example"), ("port", "80"), ("timeout", "5s")];
+        ui.kv_list(&pairs, Align::Left, ' ');
+        ui.label("end");
+        assert_eq!(row(&buf, 0), "host   : example");
+        assert_eq!(row(&buf, 1), "port   : 80     ");
+        assert_eq!(row(&buf, 2), "timeout: 5s     ");
+        assert_eq!(row(&buf, 3), "end             ");
+    }
+    #[test]
+    fn adversarial_sizes_dont_panic() {
+        let mut buf = ScreenBuffer::new(8, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.add(Label::from("abc").with_width(usize::MAX));
+        ui.add(
+            Label::from("abc")
+                .with_width(usize::MAX)
+                .align_outer(Align::Right),
+        );
+        ui.with_available(Some(usize::MAX), Some(0), |ui| {
+            ui.add(Label::from("abc").with_width(2).align_outer(Align::Right));
+            ui.add(
+                Label::from("abc")
+                    .direction(Direction::Rtl)
+                    .align_inner(Align::Right),
+            );
+            ui.frame(
+                usize::MAX,
+                BorderKind::Full,
+                StretchHint::Full,
+                (1, 1),
+                |ui| ui.label("x"),
+            );
+            ui.frame(
+                0,
+                BorderKind::Full,
+                StretchHint::Compact,
+                (usize::MAX, usize::MAX),
+                |ui| {
+                    ui.horizontal(|ui| ui.label("y"));
+                },
+            );
+        });
+        assert_eq!(row(&buf, 0), "abc     ");
+    }
+    #[test]
+    fn region_text_trims_rows() {
+        let mut buf = ScreenBuffer::new(8, 3);
+        buf.write_str(0, 0, "skip");
+        buf.write_str(2, 1, "ab");
+        buf.write_str(1, 2, "wxyz");
+        assert_eq!(buf.region_text(Rect::new(1, 1, 4, 2)), " ab\nwxyz");
+    }
+    #[test]
+    fn measure_past_target_edges() {
+        let mut buf = ScreenBuffer::new(8, 5);
+        let mut ui = Ui::new(&mut buf, 0, 2);
+        let h = ui.measure_height(|ui| {
+            for i in 0..10 {
+                ui.label(&format!("{i}"));
+            }
+        });
+        assert_eq!(h, 10);
+        assert_eq!(ui.measure(|ui| ui.label("longer than eight")), (17, 1));
+        assert_eq!((ui.cursor_y, ui.max_y), (2, 2));
+    }
+    #[test]
+    fn measure_height_of_vertical_block() {
+        let mut buf = ScreenBuffer::new(8, 5);
+        let mut ui = Ui::new(&mut buf, 0, 1);
+        let h = ui.measure_height(|ui| {
+            ui.vertical(|ui| {
+                ui.label("one");
+                ui.label("two");
+                ui.label("three");
+            })
+        });
+        assert_eq!(h, 3);
+        assert_eq!((ui.cursor_y, ui.max_y), (1, 1));
+        assert_eq!(row(&buf, 1), "        ");
+    }
+    #[test]
+    fn fps_from_known_timestamps() {
+        let start = Instant::now();
+        let mut counter = FpsCounter::new(4);
+        assert_eq!(counter.fps(), 0.0);
+        // an early slow frame drops out of the window
+        counter.tick_at(start);
+        for i in 1..=4 {
+            counter.tick_at(start + Duration::from_secs(1) + Duration::from_millis(20 * i));
+        }
+        assert!((counter.fps() - 50.0).abs() < 1e-6);
 
-    fn current_position(&self) -> (usize, usize) {
-        let mut wx = self.x;
-        for col in 0..self.current_col {
-            wx += self.col_widths[col] + self.gap_x;
+        let mut buf = ScreenBuffer::new(10, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.fps_overlay(&counter);
+        assert_eq!(row(&buf, 0), "    50 fps");
+    }
+    #[test]
+    fn show_if_reserves_space() {
+        let mut buf = ScreenBuffer::new(12, 1);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.horizontal(|ui| {
+            ui.show_if(false, true, |ui| ui.label("hidden"));
+            ui.label("a");
+            ui.show_if(false, false, |ui| ui.label("gone"));
+            ui.label("b");
+            ui.show_if(true, true, |ui| ui.label("shown"));
+        });
+        assert_eq!(row(&buf, 0), "      abshow");
+    }
+    #[test]
+    fn show_if_reserves_full_size_near_bottom() {
+        let block = |ui: &mut Ui<ScreenBuffer>| {
+            for i in 0..5 {
+                ui.label(&format!("line {i}"));
+            }
+        };
+        let mut buf = ScreenBuffer::new(8, 4);
+        let mut ui = Ui::new(&mut buf, 0, 3);
+        let hidden = ui.measure(|ui| ui.show_if(false, true, block));
+        let shown = ui.measure(|ui| ui.show_if(true, true, block));
+        assert_eq!(hidden, (6, 5));
+        assert_eq!(hidden, shown);
+        ui.show_if(false, true, block);
+        assert_eq!((ui.cursor_y, ui.max_y), (4, 4));
+    }
+    #[test]
+    fn number_spin_glyphs_and_hit_areas() {
+        let mut buf = ScreenBuffer::new(10, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        let (dec, inc) = ui.number_spin(42, 4, true);
+        ui.number_spin(-7, 3, false);
+        assert_eq!(row(&buf, 0), "◄   42 ►  ");
+        assert_eq!(row(&buf, 1), "<  -7 >   ");
+        assert_eq!((dec, inc), (Rect::new(0, 0, 1, 1), Rect::new(7, 0, 1, 1)));
+    }
+    #[test]
+    fn numbered_lines_gutter() {
+        let mut buf = ScreenBuffer::new(10, 12);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        let lines: Vec<String> = (1..=12).map(|i| format!("line {i}")).collect();
+        let lines: Vec<&str> = lines.iter().map(String::as_str).collect();
+        ui.numbered_lines(&lines, 1, 9);
+        assert_eq!(row(&buf, 0), " 1 │ line ");
+        assert_eq!(row(&buf, 11), "12 │ line ");
+    }
+    #[test]
+    fn anchor_bottom_right() {
+        let mut buf = ScreenBuffer::new(40, 10);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.label("top");
+        ui.anchor(Corner::BottomRight, |ui| ui.label("12:00"));
+        assert_eq!((ui.cursor_x, ui.cursor_y), (0, 1));
+        ui.label("next");
+        assert_eq!(row(&buf, 9), format!("{:>40}", "12:00"));
+        assert_eq!(row(&buf, 1), format!("{:40}", "next"));
+    }
+    #[test]
+    fn anchor_draws_on_the_target() {
+        let mut buf = ScreenBuffer::new(6, 2);
+        buf.set_char_width('→', 2);
+        let mut log = Vec::new();
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.capture_a11y(&mut log);
+        ui.measure(|ui| ui.anchor(Corner::BottomLeft, |ui| ui.label("no")));
+        ui.anchor(Corner::TopRight, |ui| ui.label("a→"));
+        assert_eq!(log.len(), 1);
+        assert_eq!(log[0].rect, Rect::new(3, 0, 3, 1));
+        assert_eq!(row(&buf, 0), "   a→\0");
+        assert_eq!(row(&buf, 1), "      ");
+    }
+    #[test]
+    fn throttled_flush_skips_rapid_calls() {
+        let mut buf = ScreenBuffer::new(1, 1);
+        let start = Instant::now();
+        let at = |ms| start + Duration::from_millis(ms);
+        let interval = Duration::from_millis(16);
+        let flushed: Vec<bool> = [0, 5, 10, 16, 20, 40]
+            .into_iter()
+            .map(|ms| buf.flush_throttled_at(at(ms), interval))
+            .collect();
+        assert_eq!(flushed, [true, false, false, true, false, true]);
+        assert!(!buf.flush_pending());
+        // the last update lands inside the interval and is only shown by the trailing flush
+        assert!(!buf.flush_throttled_at(at(41), interval));
+        assert!(buf.flush_pending());
+        assert!(!buf.flush_pending());
+    }
+    #[test]
+    fn degenerate_buffers() {
+        for (w, h) in [(0, 0), (1, 1), (0, 5), (5, 0), (2, 2)] {
+            let mut buf = ScreenBuffer::new(w, h);
+            buf.draw_frame(0, 0, w, h);
+            buf.draw_frame(0, 0, 1, 1);
+            buf.draw_frame(0, 0, 0, 3);
+            buf.write_str(0, 0, "hello");
+            buf.write_i64_right(0, 0, -12, 3);
+            buf.write_f64_right(0, 0, 1.5, 3, 1);
+            buf.draw_hline_capped(0, 0, 3, '<', '>', '-');
+            buf.draw_line(0, 0, 3, 3, '*');
+            buf.fill_triangle(Point::new(0, 0), Point::new(4, 0), Point::new(0, 4), '#');
+            buf.flush();
+            let mut ui = Ui::new(&mut buf, 0, 0);
+            ui.frame(1, BorderKind::Full, StretchHint::Full, (0, 0), |ui| {
+                ui.label("x")
+            });
+            ui.grid(2, 1, GridLines::Inner, GridFill::RowMajor, |grid| {
+                grid.cell(|ui| ui.label("y"))
+            });
+            ui.linechart(&[1.0, 2.0], 3, 2);
+            assert_eq!(buf.to_string().lines().count(), h);
         }
-        let mut wy = self.y;
-        for row in 0..self.current_row {
-            wy += self.row_heights[row] + self.gap_y;
+        let mut buf = ScreenBuffer::new(1, 1);
+        buf.draw_frame(0, 0, 1, 1);
+        assert_eq!(buf.to_string(), "┘\n");
+    }
+    #[test]
+    fn place_fixed_sections() {
+        let mut buf = ScreenBuffer::new(10, 3);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.horizontal(|ui| {
+            ui.place((4, 2), |ui| {
+                ui.label("left side");
+                ui.label("ab");
+            });
+            ui.place((3, 1), |ui| ui.label("r"));
+            ui.label("|");
+        });
+        assert_eq!(ui.cursor_y, 2);
+        assert_eq!(row(&buf, 0), "leftr  |  ");
+        assert_eq!(row(&buf, 1), "ab        ");
+    }
+    #[test]
+    fn diff_two_cells() {
+        let a = ScreenBuffer::new(4, 3);
+        let mut b = ScreenBuffer::new(4, 3);
+        b.put_char(1, 0, 'x');
+        b.put_char(3, 2, 'y');
+        assert_eq!(
+            a.diff(&b),
+            [(1, 0, Cell::plain('x')), (3, 2, Cell::plain('y'))]
+        );
+        assert!(b.diff(&b).is_empty());
+    }
+    #[test]
+    fn diff_carries_colors() {
+        let mut a = ScreenBuffer::new(3, 1);
+        a.write_str(0, 0, "abc");
+        let mut b = ScreenBuffer::new(3, 1);
+        b.write_str(0, 0, "abc");
+        b.put_char_styled(1, 0, 'b', Some(Color::Red), Some(Color::Blue));
+        let patch = a.diff(&b);
+        assert_eq!(patch.len(), 1);
+        assert_eq!(patch[0].2.fg(), Some(Color::Red));
+        a.apply_patch(&patch);
+        assert!(a.diff(&b).is_empty());
+        assert_eq!(a.cells[1].bg(), Some(Color::Blue));
+        // a link change alone shows up too, but the patch can't restore it
+        b.put_link(2, 0, "c", "https://example.com");
+        assert_eq!(a.diff(&b), [(2, 0, Cell::plain('c'))]);
+    }
+    #[test]
+    fn patch_round_trip() {
+        let mut a = ScreenBuffer::new(6, 2);
+        a.write_str(0, 0, "before");
+        let mut b = ScreenBuffer::new(6, 2);
+        b.write_str(0, 0, "befits");
+        b.write_str(2, 1, "日");
+        let mut patch = a.diff(&b);
+        patch.push((9, 9, Cell::plain('!')));
+        a.apply_patch(&patch);
+        assert!(a.diff(&b).is_empty());
+        assert_eq!(a.to_string(), b.to_string());
+    }
+    #[test]
+    fn plain_output_has_no_escapes() {
+        let mut buf = ScreenBuffer::new(3, 1);
+        buf.write_str(0, 0, "abc");
+        buf.set_tty_mode(TtyMode::Plain);
+        let mut out = Vec::new();
+        buf.write_screen(&mut out, buf.ansi()).unwrap();
+        assert_eq!(out, b"abc\n");
+        buf.set_tty_mode(TtyMode::Ansi);
+        let mut out = Vec::new();
+        buf.write_screen(&mut out, buf.ansi()).unwrap();
+        assert!(out.starts_with(b"\x1B[2J\x1B[H"));
+    }
+    #[test]
+    fn colored_runs_emit_sgr_on_change() {
+        let mut buf = ScreenBuffer::new(5, 2);
+        buf.put_char_fg(0, 0, 'a', Color::Red);
+        buf.put_char_fg(1, 0, 'b', Color::Red);
+        buf.put_char(2, 0, 'c');
+        buf.put_char_fg(3, 0, 'd', Color::BrightBlue);
+        buf.write_str(0, 1, "plain");
+        assert_eq!(buf.cells[0].fg(), Some(Color::Red));
+        let mut out = Vec::new();
+        buf.write_screen(&mut out, false).unwrap();
+        assert_eq!(out, b"abcd \nplain\n");
+        let mut out = Vec::new();
+        buf.write_screen(&mut out, true).unwrap();
+        let frame = String::from_utf8(out).unwrap();
+        assert_eq!(
+            frame,
+            "\x1B[2J\x1B[H\x1B[31mab\x1B[0mc\x1B[94md\x1B[0m \nplain\n"
+        );
+        // drawing over a colored cell without a color clears it
+        buf.put_char(0, 0, 'x');
+        assert_eq!(buf.cells[0].fg(), None);
+    }
+    #[test]
+    fn background_runs_coalesce() {
+        let mut buf = ScreenBuffer::new(6, 1);
+        for x in 0..3 {
+            buf.put_char_bg(x, 0, 'x', Color::Blue);
         }
-        (wx, wy)
+        buf.put_char_styled(3, 0, 'y', Some(Color::Yellow), Some(Color::Blue));
+        buf.put_char_fg(4, 0, 'z', Color::Yellow);
+        assert_eq!(buf.cells[3].bg(), Some(Color::Blue));
+        let mut out = Vec::new();
+        buf.write_screen(&mut out, true).unwrap();
+        let frame = String::from_utf8(out).unwrap();
+        assert_eq!(frame.matches("\x1B[44m").count(), 1);
+        assert_eq!(
+            frame,
+            "\x1B[2J\x1B[H\x1B[44mxxx\x1B[33my\x1B[0m\x1B[33mz\x1B[0m \n"
+        );
     }
-    fn widget<W: Widget>(&mut self, buf: &mut ScreenBuffer, widget: &W) {
-        let (wx, wy) = self.current_position();
-        widget.render(buf, wx, wy);
-
-        // keep max width, height per column, row
-        self.col_widths[self.current_col] = self.col_widths[self.current_col].max(widget.width());
-        if self.row_heights.len() <= self.current_row {
-            self.row_heights.push(widget.height());
-        } else {
-            self.row_heights[self.current_row] =
-                self.row_heights[self.current_row].max(widget.height());
+    #[test]
+    fn links_wrapped_in_osc8() {
+        let mut buf = ScreenBuffer::new(8, 1);
+        buf.write_str(0, 0, "see ");
+        buf.put_link(4, 0, "docs", "https://example.com");
+        let mut out = Vec::new();
+        buf.write_screen(&mut out, true).unwrap();
+        assert_eq!(out, b"\x1B[2J\x1B[Hsee docs\n");
+        buf.set_hyperlinks(true);
+        let mut out = Vec::new();
+        buf.write_screen(&mut out, true).unwrap();
+        let frame = String::from_utf8(out).unwrap();
+        assert_eq!(
+            frame,
+            "\x1B[2J\x1B[Hsee \x1B]8;;https://example.com\x07docs\x1B]8;;\x07\n"
+        );
+        assert_eq!(buf.to_string(), "see docs\n");
+    }
+    #[test]
+    fn link_url_control_chars_dropped() {
+        let mut buf = ScreenBuffer::new(2, 1);
+        buf.set_hyperlinks(true);
+        buf.put_link(0, 0, "go", "http://a\x07\x1B[2J\x1B]8;;http://b");
+        let mut out = Vec::new();
+        buf.write_screen(&mut out, true).unwrap();
+        let frame = String::from_utf8(out).unwrap();
+        assert_eq!(
+            frame,
+            "\x1B[2J\x1B[H\x1B]8;;http://a[2J]8;;http://b\x07go\x1B]8;;\x07\n"
+        );
+    }
+    #[test]
+    fn kv_list_right_aligned_leaders() {
+        let mut buf = ScreenBuffer::new(40, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.with_available(Some(40), None, |ui| {
+            ui.kv_list(&[("CPU", "42%"), ("Memory", "1.2 GiB")], Align::Right, '.');
+        });
+        assert_eq!(row(&buf, 0), format!("CPU {} 42%", ".".repeat(32)));
+        assert_eq!(row(&buf, 1), format!("Memory {} 1.2 GiB", ".".repeat(25)));
+    }
+    #[test]
+    fn number_field_past_the_edge() {
+        let mut buf = ScreenBuffer::new(10, 2);
+        buf.write_str(0, 0, "0123456789");
+        buf.write_i64_right(8, 0, -42, 100);
+        buf.write_i64_right(8, 1, 7, usize::MAX);
+        buf.write_f64_right(8, 1, 1.5, usize::MAX, 2);
+        // the field ends off-screen, so only blanks are visible
+        assert_eq!(row(&buf, 0), "01234567  ");
+        buf.write_i64_right(6, 1, -42, 4);
+        assert_eq!(row(&buf, 1), "       -42");
+    }
+    #[test]
+    fn virtual_buffer_window() {
+        let mut buf = VirtualBuffer::new(8, 100);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        for i in 0..50 {
+            ui.label(&format!("row {i}"));
         }
+        assert_eq!(buf.len(), 50);
+        let window: Vec<String> = buf
+            .view(40, 10)
+            .map(|row| row.iter().map(Cell::ch).collect())
+            .collect();
+        assert_eq!(window.len(), 10);
+        assert_eq!(window[0], "row 40  ");
+        assert_eq!(window[9], "row 49  ");
+        assert_eq!(buf.view(45, 10).count(), 5);
+    }
+    #[test]
+    fn virtual_buffer_bottom_anchor() {
+        let mut buf = VirtualBuffer::new(4, 60);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        let text = String::from("x");
+        ui.anchor(Corner::BottomLeft, |ui| ui.label(&text));
+        buf.put_char(0, usize::MAX, '!');
+        assert_eq!(buf.len(), 60);
+        assert_eq!(buf.view(59, 1).next().unwrap()[0].ch(), 'x');
+    }
+    #[test]
+    fn decimal_points_align() {
+        let mut buf = ScreenBuffer::new(8, 3);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.number_f64_column(&[1.5, 123.25, -99999.0], 2, 4, 3);
+        assert_eq!(row(&buf, 0), "   1.50 ");
+        assert_eq!(row(&buf, 1), " 123.25 ");
+        assert_eq!(row(&buf, 2), "########");
+        assert_eq!(row(&buf, 0).find('.'), row(&buf, 1).find('.'));
 
-        self.current_col += 1;
-        if self.current_col >= self.cols {
-            self.current_col = 0;
-            self.current_row += 1;
+        let mut buf = ScreenBuffer::new(5, 1);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.number_f64_column(&[1.256], 3, 2, 2);
+        assert_eq!(row(&buf, 0), " 1.26");
+    }
+    #[test]
+    fn frame_written_at_once() {
+        struct Writes(Vec<usize>);
+        impl Write for Writes {
+            fn write(&mut self, data: &[u8]) -> io::Result<usize> {
+                self.0.push(data.len());
+                Ok(data.len())
+            }
+            fn flush(&mut self) -> io::Result<()> {
+                Ok(())
+            }
         }
+        let buf = ScreenBuffer::new(30, 20);
+        let mut out = Writes(Vec::new());
+        buf.write_screen(&mut out, true).unwrap();
+        assert_eq!(out.0, [7 + 31 * 20]);
+    }
+    #[test]
+    fn write_str_safe_replaces_escapes() {
+        let mut buf = ScreenBuffer::new(12, 1);
+        let replaced = buf.write_str_safe(0, 0, "a\x1B[2Jb\x07c");
+        assert_eq!(replaced, 2);
+        assert_eq!(row(&buf, 0), "a␦[2Jb␦c    ");
+        assert_eq!(buf.write_str_safe(0, 0, "plain"), 0);
+        assert_eq!(buf.write_str_safe(0, 0, "a\tb        "), 0);
+        assert_eq!(row(&buf, 0), "a b         ");
+        assert_eq!(buf.write_str_safe(0, 0, "a\nb"), 1);
+        assert_eq!(row(&buf, 0), "a␦b         ");
+    }
+    #[test]
+    fn overlay_shows_background_through() {
+        let mut background = ScreenBuffer::new(6, 2);
+        background.write_str(0, 0, "......");
+        background.write_str(0, 1, "......");
+        let mut layer = ScreenBuffer::new_transparent(3, 2);
+        layer.write_str(0, 0, "HI");
+        layer.put_char(2, 1, ' ');
+        assert_eq!(layer.to_string(), "HI \n   \n");
+        background.overlay(&layer, 2, 0);
+        background.overlay(&layer, 5, 1);
+        assert_eq!(row(&background, 0), "..HI..");
+        assert_eq!(row(&background, 1), ".... H");
+    }
+    #[test]
+    fn crlf_rows() {
+        let mut buf = ScreenBuffer::new(2, 2);
+        buf.write_str(0, 0, "ab");
+        assert_eq!(buf.to_string_with_sep("\r\n"), "ab\r\n  \r\n");
+        assert_eq!(buf.to_string_with_sep(""), "ab  ");
+        assert_eq!(buf.to_string(), buf.to_string_with_sep("\n"));
+        buf.set_trailing_newline(false);
+        assert_eq!(buf.to_string(), "ab\n  ");
+        assert_eq!(buf.to_string_with_sep("\r\n"), "ab\r\n  ");
+    }
+    #[test]
+    fn hline_caps() {
+        let mut buf = ScreenBuffer::new(8, 2);
+        buf.draw_hline_capped(1, 0, 6, '├', '┤', '─');
+        buf.draw_hline_capped(0, 1, 1, '●', '●', '─');
+        assert_eq!(row(&buf, 0), " ├────┤ ");
+        assert_eq!(row(&buf, 1), "●       ");
+    }
+    #[test]
+    fn grid_column_major() {
+        let mut buf = ScreenBuffer::new(8, 3);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        let fill = GridFill::ColumnMajor { rows: 3 };
+        ui.grid(0, 0, GridLines::None, fill, |grid| {
+            for name in ["a", "b", "c", "dd", "e", "f"] {
+                grid.cell(|ui| ui.label(name));
+            }
+        });
+        assert_eq!((ui.max_x, ui.max_y), (3, 3));
+        assert_eq!(row(&buf, 0), "add     ");
+        assert_eq!(row(&buf, 1), "be      ");
+        assert_eq!(row(&buf, 2), "cf      ");
+    }
+    #[test]
+    fn grid_side_effect_runs_once() {
+        let mut buf = ScreenBuffer::new(10, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        let (passes, effects) = (std::cell::Cell::new(0), std::cell::Cell::new(0));
+        ui.grid(2, 0, GridLines::None, GridFill::RowMajor, |grid| {
+            passes.set(passes.get() + 1);
+            if !grid.is_measuring() {
+                effects.set(effects.get() + 1);
+            }
+            grid.cell(|ui| ui.label("a"));
+        });
+        assert_eq!((passes.get(), effects.get()), (2, 1));
+    }
+    #[test]
+    fn grid_cell_borders() {
+        let mut buf = ScreenBuffer::new(9, 5);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.grid(2, 0, GridLines::Cells, GridFill::RowMajor, |grid| {
+            for name in ["a", "bb", "ccc", "d"] {
+                grid.cell(|ui| ui.label(name));
+            }
+        });
+        assert_eq!((ui.max_x, ui.max_y), (8, 5));
+        assert_eq!(row(&buf, 0), "┌───┬──┐ ");
+        assert_eq!(row(&buf, 1), "│a  │bb│ ");
+        assert_eq!(row(&buf, 2), "├───┼──┤ ");
+        assert_eq!(row(&buf, 3), "│ccc│d │ ");
+        assert_eq!(row(&buf, 4), "└───┴──┘ ");
+    }
+    #[test]
+    fn grid_partial_last_row() {
+        let mut buf = ScreenBuffer::new(20, 10);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.grid(3, 0, GridLines::None, GridFill::RowMajor, |grid| {
+            for _ in 0..4 {
+                grid.cell(|ui| ui.label("ab"));
+            }
+        });
+        assert_eq!(ui.max_y, 2);
+        assert_eq!(ui.cursor_y, 2);
+        ui.label("x");
+        assert_eq!(row(&buf, 0), format!("{:<20}", "ababab"));
+        assert_eq!(row(&buf, 1), format!("{:<20}", "ab"));
+        assert_eq!(row(&buf, 2), format!("{:<20}", "x"));
     }
+    #[test]
+    fn number_auto_precision() {
+        assert_eq!(format_auto(1234.5678, 7), "1234.57");
+        assert_eq!(format_auto(0.001234, 7), "0.00123");
+        assert_eq!(format_auto(123456789.0, 7), "1.235e8");
+        assert_eq!(format_auto(f64::NEG_INFINITY, 4), "-inf");
+        assert_eq!(format_auto(f64::NEG_INFINITY, 2), "##");
+        assert_eq!(format_auto(f64::NAN, 2), "##");
 
-    fn write_str(&mut self, buf: &mut ScreenBuffer, text: &str) {
-        let widget = TextWidget::from(text);
-        self.widget(buf, &widget);
+        let mut buf = ScreenBuffer::new(10, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.number_auto(1.5, 7);
+        ui.number_auto(-0.001234, 7);
+        assert_eq!(row(&buf, 0), "1.50000   ");
+        assert_eq!(row(&buf, 1), "-0.0012   ");
     }
-}
-impl Layout for GridLayout {
-    fn width(&self) -> usize {
-        self.col_widths.iter().sum::<usize>() + self.cols.saturating_sub(1) * self.gap_x
+    #[test]
+    fn draw_line_diagonal() {
+        let mut buf = ScreenBuffer::new(6, 6);
+        buf.draw_line(0, 0, 4, 4, '*');
+        for i in 0..6 {
+            let expected = if i < 5 { '*' } else { ' ' };
+            assert_eq!(buf.cells[buf.index(i, i)].ch, expected);
+        }
+        assert_eq!(buf.cells.iter().filter(|c| c.ch == '*').count(), 5);
+        // clipped to the buffer
+        buf.draw_line(3, 0, 9, 2, '#');
+        assert_eq!(row(&buf, 0), "*  ## ");
+    }
+    #[test]
+    fn braille_canvas_cell() {
+        let mut canvas = BrailleCanvas::new(2, 1);
+        canvas.set(0, 0);
+        canvas.set(1, 0);
+        canvas.set(0, 3);
+        canvas.set(1, 3);
+        canvas.set(9, 9);
+        let mut buf = ScreenBuffer::new(3, 1);
+        canvas.render(&mut buf, 0, 0);
+        assert_eq!(row(&buf, 0), "\u{28C9}  ");
     }
+    #[test]
+    fn linechart_rising() {
+        let mut buf = ScreenBuffer::new(4, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.linechart(&[1.0, 2.0, 3.0, 4.0], 4, 2);
+        assert_eq!(ui.max_x, 4);
+        assert_eq!(ui.max_y, 2);
+        // starts in the bottom-left cell and ends in the top-right one
+        assert_eq!(row(&buf, 0).chars().next(), Some(' '));
+        assert_ne!(row(&buf, 1).chars().next(), Some(' '));
+        assert_ne!(row(&buf, 0).chars().last(), Some(' '));
+        assert_eq!(row(&buf, 1).chars().last(), Some(' '));
 
-    fn height(&self) -> usize {
-        self.row_heights.iter().sum::<usize>()
-            + self.row_heights.len().saturating_sub(1) * self.gap_y
+        let mut buf = ScreenBuffer::new(4, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.linechart(&[], 4, 2);
+        ui.linechart(&[5.0], 4, 2);
+        assert_eq!(ui.max_y, 4);
+        assert_eq!(row(&buf, 0), "    ");
+    }
+    #[test]
+    fn scatter_corners() {
+        let mut buf = ScreenBuffer::new(3, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.scatter(
+            &[(0.0, 0.0), (f64::NAN, 3.0), (4.0, 6.0), (10.0, 10.0)],
+            3,
+            2,
+        );
+        // (0, 0) is the bottom-left dot, (10, 10) the top-right one
+        assert_eq!(buf.cells[buf.index(0, 1)].ch, '\u{2840}');
+        assert_eq!(buf.cells[buf.index(2, 0)].ch, '\u{2808}');
     }
+    #[test]
+    fn grid_lines_between_cells() {
+        let mut buf = ScreenBuffer::new(5, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.grid(2, 0, GridLines::Inner, GridFill::RowMajor, |grid| {
+            for text in ["a", "bb", "c", "d"] {
+                grid.cell(|ui| ui.label(text));
+            }
+        });
+        assert_eq!(ui.cursor_y, 3);
+        assert_eq!(row(&buf, 0), "a│bb ");
+        assert_eq!(row(&buf, 1), "─┼── ");
+        assert_eq!(row(&buf, 2), "c│d  ");
+    }
+    #[test]
+    fn paragraph_full_justify() {
+        assert_eq!(
+            justify_line(&["the", "quick", "brown", "fox"], 20, text::display_width),
+            "the  quick brown fox"
+        );
+        assert_eq!(justify_line(&["single"], 20, text::display_width), "single");
 
-    fn init_position_from_other(&mut self, x: usize, y: usize) {
-        self.y = y;
-        self.x = x;
+        let mut buf = ScreenBuffer::new(20, 3);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.paragraph(
+            "the quick brown fox jumps over the lazy dog",
+            20,
+            Justify::Full,
+            Align::Left,
+        );
+        assert_eq!(ui.cursor_y, 3);
+        assert_eq!(row(&buf, 0), "the  quick brown fox");
+        assert_eq!(row(&buf, 1), "jumps  over the lazy");
+        assert_eq!(row(&buf, 2), format!("{:<20}", "dog"));
+    }
+    #[test]
+    fn paragraph_centered_lines() {
+        let mut buf = ScreenBuffer::new(20, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        let quote = "simplicity is prerequisite for reliability";
+        ui.paragraph(quote, 20, Justify::Left, Align::Center);
+        ui.paragraph("end", 20, Justify::Full, Align::Right);
+        assert_eq!(row(&buf, 0), "   simplicity is    ");
+        assert_eq!(row(&buf, 1), "  prerequisite for  ");
+        assert_eq!(row(&buf, 2), "    reliability     ");
+        assert_eq!(row(&buf, 3), format!("{:>20}", "end"));
+    }
+    #[test]
+    fn remaining_after_label() {
+        let mut buf = ScreenBuffer::new(40, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.available_x = Some(40);
+        assert_eq!(ui.remaining(), (Some(40), None));
+        ui.horizontal(|ui| {
+            ui.label("0123456789");
+            assert_eq!(ui.remaining(), (Some(30), None));
+        });
+    }
+    #[test]
+    fn advance_saturates_at_buffer() {
+        let mut buf = ScreenBuffer::new(10, 5);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        for _ in 0..8 {
+            ui.label("abc");
+        }
+        ui.space(usize::MAX);
+        assert_eq!(ui.max_y, 5);
+        assert_eq!(ui.cursor_y, 5);
+        ui.horizontal(|ui| ui.space(usize::MAX));
+        assert_eq!(ui.max_x, 10);
     }
-    fn init_position_other_layout<L: Layout>(&self, layout: &mut L) {
-        let (x, y) = self.current_position();
-        layout.init_position_from_other(x, y);
+    #[test]
+    fn frame_selected_sides() {
+        let mut buf = ScreenBuffer::new(6, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.frame(
+            1,
+            BorderKind::Sides(Sides::TOP | Sides::LEFT),
+            StretchHint::Compact,
+            (0, 0),
+            |ui| ui.label("ab"),
+        );
+        assert_eq!(row(&buf, 0), "+---  ");
+        assert_eq!(row(&buf, 1), "|ab   ");
+        assert_eq!(row(&buf, 2), "|     ");
+        assert_eq!(row(&buf, 3), "      ");
     }
+    #[test]
+    fn log_view_follows_tail() {
+        let mut log = LogView::new(5);
+        for i in 0..8 {
+            log.push(format!("line {}", i));
+        }
+        assert_eq!(log.len(), 5);
 
-    fn update_self_size_from_other<L: Layout>(&mut self, layout: &L) {
-        self.col_widths[self.current_col] = self.col_widths[self.current_col].max(layout.width());
-        self.row_heights[self.current_row] =
-            self.row_heights[self.current_row].max(layout.height());
+        let mut buf = ScreenBuffer::new(8, 6);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        log.render(&mut ui, 8, 3, true);
+        log.scroll_up(2);
+        log.render(&mut ui, 8, 3, false);
+        assert_eq!(row(&buf, 0), "line 5  ");
+        assert_eq!(row(&buf, 1), "line 6  ");
+        assert_eq!(row(&buf, 2), "line 7  ");
+        assert_eq!(row(&buf, 3), "line 3  ");
+        assert_eq!(row(&buf, 5), "line 5  ");
     }
-}
+    #[test]
+    fn frame_round_trip() {
+        let mut first = ScreenBuffer::new(4, 2);
+        first.write_str(0, 0, "ab┼");
+        first.write_str(1, 1, "xyz");
+        let mut second = ScreenBuffer::new(2, 1);
+        second.write_str(0, 0, "ok");
 
-pub trait Widget {
-    fn width(&self) -> usize;
-    fn height(&self) -> usize;
-    fn render(&self, buf: &mut ScreenBuffer, x: usize, y: usize);
-}
+        let mut recording = Vec::new();
+        first.write_frame(&mut recording).unwrap();
+        second.write_frame(&mut recording).unwrap();
 
-struct TextWidget<'a> {
-    text: &'a str,
-}
-impl<'a> Widget for TextWidget<'a> {
-    fn width(&self) -> usize {
-        self.text.len()
+        let mut src = recording.as_slice();
+        let read = ScreenBuffer::read_frame(&mut src).unwrap();
+        assert_eq!((read.width, read.height), (4, 2));
+        assert_eq!(row(&read, 0), "ab┼ ");
+        assert_eq!(row(&read, 1), " xyz");
+        let read = ScreenBuffer::read_frame(&mut src).unwrap();
+        assert_eq!(row(&read, 0), "ok");
+        assert!(ScreenBuffer::read_frame(&mut src).is_err());
+    }
+    #[test]
+    fn read_frame_rejects_huge_headers() {
+        for header in ["18446744073709551615 2\n", "100000 100000\n"] {
+            let err = ScreenBuffer::read_frame(header.as_bytes()).err().unwrap();
+            assert_eq!(err.kind(), io::ErrorKind::InvalidData);
+        }
+    }
+    #[test]
+    fn player_steps_frames() {
+        let mut recording = Vec::new();
+        for text in ["one", "two"] {
+            let mut frame = ScreenBuffer::new(3, 1);
+            frame.write_str(0, 0, text);
+            frame.write_frame(&mut recording).unwrap();
+        }
+        let mut player = Player::from_reader(recording.as_slice(), Duration::ZERO).unwrap();
+        assert_eq!(player.len(), 2);
+        assert_eq!(row(player.step().unwrap(), 0), "one");
+        assert_eq!(row(player.step().unwrap(), 0), "two");
+        assert!(player.step().is_none());
+        player.rewind();
+        assert_eq!(row(player.step().unwrap(), 0), "one");
+        player.rewind();
+        for speed in [0.0, -1.0, f64::NAN, f64::INFINITY] {
+            player.play(speed);
+        }
+        assert_eq!(player.position, 0);
+        player.play(2.0);
+        assert!(player.step().is_none());
+    }
+    #[test]
+    fn rect_intersect_contains() {
+        let a = Rect::new(0, 0, 10, 5);
+        let b = Rect::new(6, 3, 10, 10);
+        assert_eq!(a.intersect(b), Some(Rect::new(6, 3, 4, 2)));
+        assert_eq!(a.intersect(Rect::new(10, 0, 2, 2)), None);
+        assert!(a.contains(Point::new(0, 0)));
+        assert!(a.contains(Point::new(9, 4)));
+        assert!(!a.contains(Point::new(10, 4)));
+        assert!(!a.contains(Point::new(3, 5)));
+        assert_eq!(Rect::new(20, 2, 3, 3).clamp_to(a), Rect::new(10, 2, 0, 0));
+    }
+    #[test]
+    fn lines_clip_to_buffer() {
+        let mut buf = ScreenBuffer::new(4, 3);
+        buf.draw_hline(2, 0, 10, '-');
+        buf.draw_vline(3, 1, 10, '|');
+        assert_eq!(row(&buf, 0), "  --");
+        assert_eq!(row(&buf, 1), "   |");
+        assert_eq!(row(&buf, 2), "   |");
+    }
+    #[test]
+    fn frame_min_inner() {
+        let mut buf = ScreenBuffer::new(6, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.frame(1, BorderKind::Full, StretchHint::Compact, (3, 1), |_| {});
+        assert_eq!(ui.cursor_y, 3);
+        assert_eq!(row(&buf, 0), "+---+ ");
+        assert_eq!(row(&buf, 1), "|   | ");
+        assert_eq!(row(&buf, 2), "+---+ ");
+    }
+    #[test]
+    fn frame_auto_stretch() {
+        let mut buf = ScreenBuffer::new(20, 6);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.with_available(Some(20), None, |ui| {
+            let auto = || StretchHint::Auto { threshold: 0.5 };
+            ui.frame(1, BorderKind::Full, auto(), (0, 0), |ui| ui.label("narrow"));
+            ui.frame(1, BorderKind::Full, auto(), (0, 0), |ui| {
+                ui.label("rather wide")
+            });
+        });
+        assert_eq!(row(&buf, 0), format!("+{}+", "-".repeat(18)));
+        assert_eq!(row(&buf, 1), format!("|narrow{}|", " ".repeat(12)));
+        assert_eq!(
+            row(&buf, 3),
+            format!("+{}+{}", "-".repeat(11), " ".repeat(7))
+        );
+        assert_eq!(row(&buf, 4), "|rather wide|       ");
     }
+    #[test]
+    fn scroll_frame_window() {
+        let mut buf = ScreenBuffer::new(8, 7);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.scroll_frame(4, 3, 10, |ui| {
+            for i in 0..10 {
+                ui.label(&format!("l{i}"));
+            }
+        });
+        ui.label("after");
+        assert_eq!(row(&buf, 0), "+------+");
+        assert_eq!(row(&buf, 1), "|l3    ░");
+        assert_eq!(row(&buf, 2), "|l4    █");
+        assert_eq!(row(&buf, 4), "|l6    ░");
+        assert_eq!(row(&buf, 5), "+------+");
+        assert_eq!(row(&buf, 6), "after   ");
+    }
+    #[test]
+    fn frame_bounded_fits_or_scrolls() {
+        let mut buf = ScreenBuffer::new(10, 8);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.frame_bounded(10, 6, 0, |ui| ui.label("hi"));
+        ui.frame_bounded(6, 5, 2, |ui| {
+            for i in 0..6 {
+                ui.label(&format!("l{i}"));
+            }
+        });
+        assert_eq!(row(&buf, 0), "+--+      ");
+        assert_eq!(row(&buf, 1), "|hi|      ");
+        assert_eq!(row(&buf, 2), "+--+      ");
+        assert_eq!(row(&buf, 3), "+-▲+      ");
+        assert_eq!(row(&buf, 4), "|l2|      ");
+        assert_eq!(row(&buf, 6), "|l4|      ");
+        assert_eq!(row(&buf, 7), "+-▼+      ");
+    }
+    #[test]
+    fn frame_with_dividers_joins_double_border() {
+        let mut buf = ScreenBuffer::new(8, 6);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.frame_with_dividers(&[1], |ui| {
+            ui.label("head");
+            ui.space(1);
+            ui.label("body");
+        });
+        ui.label("after");
+        assert_eq!(row(&buf, 0), "╔════╗  ");
+        assert_eq!(row(&buf, 1), "║head║  ");
+        assert_eq!(row(&buf, 2), "╟────╢  ");
+        assert_eq!(row(&buf, 3), "║body║  ");
+        assert_eq!(row(&buf, 4), "╚════╝  ");
+        assert_eq!(row(&buf, 5), "after   ");
+    }
+    #[test]
+    fn frame_clips_overflow() {
+        let mut buf = ScreenBuffer::new(10, 12);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.available_y = Some(6);
+        ui.frame(1, BorderKind::Full, StretchHint::Full, (0, 0), |ui| {
+            for i in 0..10 {
+                ui.label(&format!("label {}", i));
+            }
+        });
+        assert_eq!(ui.cursor_y, 6);
+        assert_eq!(row(&buf, 1), "|label 0| ");
+        assert_eq!(row(&buf, 4), "|label 3| ");
+        assert_eq!(row(&buf, 5), "+------▼+ ");
+        assert_eq!(row(&buf, 6), "          ");
+    }
+    #[test]
+    fn label_rtl() {
+        let mut buf = ScreenBuffer::new(6, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.add(
+            Label::from("שלום")
+                .with_width(6)
+                .align_inner(Align::Right)
+                .direction(Direction::Rtl),
+        );
+        ui.add(Label::from("שלום").direction(Direction::Rtl));
+        assert_eq!(row(&buf, 0), "  םולש");
+        assert_eq!(row(&buf, 1), "םולש  ");
+    }
+    #[test]
+    fn number_f64_overflow_modes() {
+        let mut buf = ScreenBuffer::new(6, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.number_f64(12345.678, 2, 6, Overflow::Clamp);
+        ui.number_f64(12345.678, 2, 6, Overflow::Hash);
+        ui.number_f64(12345.678, 2, 6, Overflow::Ellipsis);
+        ui.number_f64(1.5, 2, 6, Overflow::Hash);
+        assert_eq!(row(&buf, 0), "345.68");
+        assert_eq!(row(&buf, 1), "######");
+        assert_eq!(row(&buf, 2), "12345…");
+        assert_eq!(row(&buf, 3), "  1.50");
+    }
+    #[test]
+    fn composite_widget_vertical() {
+        let composite = CompositeWidget::vertical(1)
+            .with(TextWidget::from("ab"))
+            .with(TextWidget::from("cdef"));
+        assert_eq!(composite.width(), 4);
+        assert_eq!(composite.height(), 3);
 
-    fn height(&self) -> usize {
-        1
+        let row_widget = CompositeWidget::horizontal(1)
+            .with(composite)
+            .with(TextWidget::from("g"));
+        assert_eq!((row_widget.width(), row_widget.height()), (6, 3));
+        let mut buf = ScreenBuffer::new(6, 3);
+        row_widget.render(&mut buf, 0, 0);
+        assert_eq!(row(&buf, 0), "ab   g");
+        assert_eq!(row(&buf, 2), "cdef  ");
+    }
+    #[test]
+    fn tab_to_aligns_columns() {
+        let mut buf = ScreenBuffer::new(50, 3);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        for (name, value) in [("name", "value"), ("longer name", "v2")] {
+            ui.horizontal(|ui| {
+                ui.tab_to(0);
+                ui.label(name);
+                ui.tab_to(20);
+                ui.label(value);
+            });
+        }
+        ui.horizontal(|ui| {
+            ui.label(&"x".repeat(25));
+            ui.tab_to(20);
+            assert_eq!(ui.cursor_x, 40);
+        });
+        assert_eq!(row(&buf, 0).trim_end(), format!("{:<20}value", "name"));
+        assert_eq!(row(&buf, 1).trim_end(), format!("{:<20}v2", "longer name"));
     }
+    #[test]
+    fn hsplit_ratios() {
+        assert_eq!(split_sizes(10, &[1, 1, 1]), vec![4, 3, 3]);
+        assert_eq!(split_sizes(10, &[0, 0]), vec![0, 0]);
 
-    fn render(&self, buf: &mut ScreenBuffer, x: usize, y: usize) {
-        buf.write_str(x, y, self.text);
+        let regions = std::cell::RefCell::new(Vec::new());
+        let mut buf = ScreenBuffer::new(100, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.available_x = Some(90);
+        ui.hsplit(&[2, 1], |i, ui| {
+            regions
+                .borrow_mut()
+                .push((i, ui.cursor_x, ui.remaining().0));
+            ui.label("x");
+        });
+        assert_eq!(
+            regions.into_inner(),
+            vec![(0, 0, Some(60)), (1, 60, Some(30))]
+        );
+        assert_eq!(ui.cursor_y, 1);
+        assert_eq!(buf.cells[60].ch, 'x');
     }
-}
-impl<'a> From<&'a str> for TextWidget<'a> {
-    fn from(value: &'a str) -> TextWidget<'a> {
-        Self { text: value }
+    #[test]
+    fn vsplit_ratios() {
+        let regions = std::cell::RefCell::new(Vec::new());
+        let mut buf = ScreenBuffer::new(10, 30);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.available_y = Some(20);
+        ui.vsplit(&[1, 1, 2], |i, ui| {
+            regions
+                .borrow_mut()
+                .push((i, ui.cursor_y, ui.remaining().1));
+            ui.label("abc");
+        });
+        assert_eq!(
+            regions.into_inner(),
+            vec![(0, 0, Some(5)), (1, 5, Some(5)), (2, 10, Some(10))]
+        );
+        assert_eq!((ui.max_x, ui.cursor_y), (3, 20));
     }
-}
-#[cfg(test)]
-mod test {
-    use super::*;
-    fn render_test<T: DrawTarget>(ui: &mut Ui<T>) {
-        let x_wide = 70;
-        ui.available_x = Some(x_wide);
+    #[test]
+    fn content_hash_matches_identical_renders() {
+        let render = |text: &str| {
+            let mut buf = ScreenBuffer::new(10, 2);
+            let mut ui = Ui::new(&mut buf, 0, 0);
+            ui.label(text);
+            ui.number_i64(42, 4);
+            buf.content_hash()
+        };
+        assert_eq!(render("status"), render("status"));
+        assert_ne!(render("status"), render("statux"));
+        assert_ne!(
+            ScreenBuffer::new(2, 3).content_hash(),
+            ScreenBuffer::new(3, 2).content_hash()
+        );
+    }
+    #[test]
+    fn rows_match_to_string() {
+        let mut buf = ScreenBuffer::new(5, 3);
+        buf.write_str(0, 0, "ab");
+        buf.write_str(2, 2, "xyz");
+        buf.write_str(0, 1, "日a");
+        let rows: Vec<String> = buf.rows().collect();
+        assert_eq!(rows, buf.to_string().lines().collect::<Vec<_>>());
+        assert_eq!(rows, vec!["ab   ", "日a  ", "  xyz"]);
+    }
+    #[test]
+    fn horizontal_spread_items() {
+        let mut buf = ScreenBuffer::new(30, 1);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.available_x = Some(30);
+        ui.horizontal_spread(&["a", "bb", "c"]);
+        assert_eq!(
+            row(&buf, 0),
+            format!("a{}bb{}c", " ".repeat(13), " ".repeat(13))
+        );
+    }
+    #[test]
+    fn label_vcenter_in_region() {
+        let mut buf = ScreenBuffer::new(6, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
         ui.horizontal(|ui| {
-            ui.space(x_wide - 1);
-            ui.label("|");
+            ui.vertical(|ui| {
+                ui.label("a");
+                ui.label("b");
+                ui.label("c");
+            });
+            ui.label_vcenter("mid", 3);
         });
+        assert_eq!(ui.cursor_y, 3);
+        assert_eq!(row(&buf, 0), "a     ");
+        assert_eq!(row(&buf, 1), "bmid  ");
+        assert_eq!(row(&buf, 2), "c     ");
+    }
+    #[test]
+    fn blank_glyph_substitution() {
+        let mut buf = ScreenBuffer::new(4, 2);
+        buf.write_str(1, 0, "ab");
+        buf.set_blank_glyph('.');
+        assert_eq!(buf.to_string(), ".ab.\n....\n");
+        assert_eq!(row(&buf, 0), " ab ");
+    }
+    #[test]
+    fn fill_right_triangle() {
+        let mut buf = ScreenBuffer::new(6, 6);
+        buf.fill_triangle(Point::new(0, 0), Point::new(4, 0), Point::new(0, 4), '#');
+        assert_eq!(row(&buf, 0), "##### ");
+        assert_eq!(row(&buf, 1), "####  ");
+        assert_eq!(row(&buf, 2), "###   ");
+        assert_eq!(row(&buf, 3), "##    ");
+        assert_eq!(row(&buf, 4), "#     ");
+        assert_eq!(row(&buf, 5), "      ");
+
+        // opposite winding, partly outside the buffer
+        let mut buf = ScreenBuffer::new(3, 3);
+        buf.fill_triangle(Point::new(0, 0), Point::new(0, 9), Point::new(9, 0), '#');
+        assert_eq!(buf.to_string(), "###\n###\n###\n");
+
+        // vertices at the far edge of the coordinate space
+        let mut buf = ScreenBuffer::new(4, 3);
+        buf.fill_triangle(
+            Point::new(0, 0),
+            Point::new(usize::MAX, 0),
+            Point::new(0, 2),
+            '#',
+        );
+        assert_eq!(buf.to_string(), "####\n####\n#   \n");
+        let mut buf = ScreenBuffer::new(2, 2);
+        buf.fill_triangle(
+            Point::new(0, 0),
+            Point::new(usize::MAX, 0),
+            Point::new(0, usize::MAX),
+            '#',
+        );
+        assert_eq!(buf.to_string(), "##\n##\n");
+    }
+    #[test]
+    fn layout_direction() {
+        let mut buf = ScreenBuffer::new(4, 4);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        assert!(!ui.is_horizontal());
         ui.horizontal(|ui| {
-            ui.vertical(|ui| {
-                ui.add(
-                    Label::from("left left no width")
-                        .align_inner(Align::Left)
-                        .align_outer(Align::Left),
-                );
-                ui.add(
-                    Label::from("left left width")
-                        .with_width(20)
-                        .align_inner(Align::Left)
-                        .align_outer(Align::Left),
-                );
-                ui.add(
-                    Label::from("right right no width")
-                        .align_inner(Align::Right)
-                        .align_outer(Align::Left),
-                );
-                ui.add(
-                    Label::from("right right width")
-                        .with_width(20)
-                        .align_inner(Align::Right)
-                        .align_outer(Align::Left),
-                );
-                ui.add(
-                    Label::from("r")
-                        .align_inner(Align::Right)
-                        .align_outer(Align::Left),
-                );
+            assert!(ui.is_horizontal());
+            ui.vertical(|ui| assert!(!ui.is_horizontal()));
+        });
+    }
+    #[test]
+    fn with_available_override() {
+        let mut buf = ScreenBuffer::new(20, 2);
+        let mut ui = Ui::new(&mut buf, 0, 0);
+        ui.with_available(Some(10), None, |ui| {
+            assert_eq!(ui.remaining(), (Some(10), None));
+            ui.add(Label::from("abc").with_width(3).align_outer(Align::Right));
+        });
+        assert_eq!(ui.remaining(), (None, None));
+        assert_eq!(row(&buf, 0), format!("{:>10}{:10}", "abc", ""));
+    }
+    fn render_test<T: DrawTarget>(ui: &mut Ui<T>) {
+        let x_wide = 70;
+        ui.with_available(Some(x_wide), None, |ui| {
+            ui.horizontal(|ui| {
+                ui.space(x_wide - 1);
+                ui.label("|");
             });
-            ui.vertical(|ui| {
-                ui.add(
-                    Label::from("left left no width")
-                        .align_inner(Align::Left)
-                        .align_outer(Align::Left),
-                );
-                ui.add(
-                    Label::from("left left width")
-                        .with_width(20)
-                        .align_inner(Align::Left)
-                        .align_outer(Align::Left),
-                );
-                ui.add(
-                    Label::from("right right no width")
-                        .align_inner(Align::Right)
-                        .align_outer(Align::Right),
-                );
-                ui.add(
-                    Label::from("right right width")
-                        .with_width(80)
-                        .align_inner(Align::Right)
-                        .align_outer(Align::Right),
-                );
-                ui.add(
-                    Label::from("r")
-                        .align_inner(Align::Right)
-                        .align_outer(Align::Right),
-                );
+            ui.horizontal(|ui| {
+                ui.vertical(|ui| {
+                    ui.add(
+                        Label::from("left left no width")
+                            .align_inner(Align::Left)
+                            .align_outer(Align::Left),
+                    );
+                    ui.add(
+                        Label::from("left left width")
+                            .with_width(20)
+                            .align_inner(Align::Left)
+                            .align_outer(Align::Left),
+                    );
+                    ui.add(
+                        Label::from("right right no width")
+                            .align_inner(Align::Right)
+                            .align_outer(Align::Left),
+                    );
+                    ui.add(
+                        Label::from("right right width")
+                            .with_width(20)
+                            .align_inner(Align::Right)
+                            .align_outer(Align::Left),
+                    );
+                    ui.add(
+                        Label::from("r")
+                            .align_inner(Align::Right)
+                            .align_outer(Align::Left),
+                    );
+                });
+                ui.vertical(|ui| {
+                    ui.add(
+                        Label::from("left left no width")
+                            .align_inner(Align::Left)
+                            .align_outer(Align::Left),
+                    );
+                    ui.add(
+                        Label::from("left left width")
+                            .with_width(20)
+                            .align_inner(Align::Left)
+                            .align_outer(Align::Left),
+                    );
+                    ui.add(
+                        Label::from("right right no width")
+                            .align_inner(Align::Right)
+                            .align_outer(Align::Right),
+                    );
+                    ui.add(
+                        Label::from("right right width")
+                            .with_width(80)
+                            .align_inner(Align::Right)
+                            .align_outer(Align::Right),
+                    );
+                    ui.add(
+                        Label::from("r")
+                            .align_inner(Align::Right)
+                            .align_outer(Align::Right),
+                    );
+                });
             });
         });
     }
@@ -0,0 +1,49 @@
+use crate::DrawTarget;
+
+/// Records every `put_char`, in order, so tests can check exactly which cells a
+/// widget touched. Writes outside the target are recorded too.
+pub struct CountingTarget {
+    width: usize,
+    height: usize,
+    pub writes: Vec<(usize, usize, char)>,
+}
+impl CountingTarget {
+    pub fn new(width: usize, height: usize) -> Self {
+        Self {
+            width,
+            height,
+            writes: Vec::new(),
+        }
+    }
+    pub fn out_of_bounds(&self) -> impl Iterator<Item = &(usize, usize, char)> {
+        self.writes
+            .iter()
+            .filter(|&&(x, y, _)| x >= self.width || y >= self.height)
+    }
+}
+impl DrawTarget for CountingTarget {
+    // clearing isn't a write, the record is kept
+    fn clear(&mut self) {}
+    fn put_char(&mut self, x: usize, y: usize, ch: char) {
+        self.writes.push((x, y, ch));
+    }
+    fn flush(&self) {}
+    fn dimensions(&self) -> (usize, usize) {
+        (self.width, self.height)
+    }
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    use crate::{Label, Ui};
+    use std::collections::HashSet;
+    #[test]
+    fn label_writes_its_width() {
+        let mut target = CountingTarget::new(10, 2);
+        let mut ui = Ui::new(&mut target, 2, 1);
+        ui.add(Label::from("abcdefg").with_width(4));
+        let cells: HashSet<_> = target.writes.iter().map(|&(x, y, _)| (x, y)).collect();
+        assert_eq!(cells, (2..6).map(|x| (x, 1)).collect());
+        assert_eq!(target.out_of_bounds().count(), 0);
+    }
+}
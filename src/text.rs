@@ -0,0 +1,235 @@
+// zero-width ranges: combining marks, joiners and variation selectors
+const ZERO_WIDTH: &[(u32, u32)] = &[
+    (0x0300, 0x036F),
+    (0x0483, 0x0489),
+    (0x0591, 0x05BD),
+    (0x05BF, 0x05BF),
+    (0x05C1, 0x05C2),
+    (0x05C4, 0x05C5),
+    (0x05C7, 0x05C7),
+    (0x0610, 0x061A),
+    (0x064B, 0x065F),
+    (0x0670, 0x0670),
+    (0x06D6, 0x06DC),
+    (0x06DF, 0x06E4),
+    (0x06E7, 0x06E8),
+    (0x06EA, 0x06ED),
+    (0x0E31, 0x0E31),
+    (0x0E34, 0x0E3A),
+    (0x0E47, 0x0E4E),
+    (0x1AB0, 0x1AFF),
+    (0x1DC0, 0x1DFF),
+    (0x200B, 0x200F),
+    (0x20D0, 0x20FF),
+    (0xFE00, 0xFE0F),
+    (0xFE20, 0xFE2F),
+    (0x1F3FB, 0x1F3FF),
+    (0xE0100, 0xE01EF),
+];
+// east asian wide/fullwidth and emoji presentation ranges
+const WIDE: &[(u32, u32)] = &[
+    (0x1100, 0x115F),
+    (0x231A, 0x231B),
+    (0x2329, 0x232A),
+    (0x23E9, 0x23EC),
+    (0x23F0, 0x23F0),
+    (0x23F3, 0x23F3),
+    (0x25FD, 0x25FE),
+    (0x2614, 0x2615),
+    (0x2648, 0x2653),
+    (0x267F, 0x267F),
+    (0x2693, 0x2693),
+    (0x26A1, 0x26A1),
+    (0x26AA, 0x26AB),
+    (0x26BD, 0x26BE),
+    (0x26C4, 0x26C5),
+    (0x26CE, 0x26CE),
+    (0x26D4, 0x26D4),
+    (0x26EA, 0x26EA),
+    (0x26F2, 0x26F3),
+    (0x26F5, 0x26F5),
+    (0x26FA, 0x26FA),
+    (0x26FD, 0x26FD),
+    (0x2705, 0x2705),
+    (0x270A, 0x270B),
+    (0x2728, 0x2728),
+    (0x274C, 0x274C),
+    (0x274E, 0x274E),
+    (0x2753, 0x2755),
+    (0x2757, 0x2757),
+    (0x2795, 0x2797),
+    (0x27B0, 0x27B0),
+    (0x27BF, 0x27BF),
+    (0x2B1B, 0x2B1C),
+    (0x2B50, 0x2B50),
+    (0x2B55, 0x2B55),
+    (0x2E80, 0x303E),
+    (0x3041, 0x33FF),
+    (0x3400, 0x4DBF),
+    (0x4E00, 0x9FFF),
+    (0xA000, 0xA4CF),
+    (0xA960, 0xA97F),
+    (0xAC00, 0xD7A3),
+    (0xF900, 0xFAFF),
+    (0xFE10, 0xFE19),
+    (0xFE30, 0xFE6F),
+    (0xFF00, 0xFF60),
+    (0xFFE0, 0xFFE6),
+    (0x1F004, 0x1F004),
+    (0x1F0CF, 0x1F0CF),
+    (0x1F18E, 0x1F18E),
+    (0x1F191, 0x1F19A),
+    (0x1F1E6, 0x1F1FF),
+    (0x1F200, 0x1F202),
+    (0x1F210, 0x1F23B),
+    (0x1F240, 0x1F248),
+    (0x1F250, 0x1F251),
+    (0x1F260, 0x1F265),
+    (0x1F300, 0x1F3FA),
+    (0x1F400, 0x1F64F),
+    (0x1F680, 0x1F6FF),
+    (0x1F7E0, 0x1F7EB),
+    (0x1F90C, 0x1F9FF),
+    (0x1FA70, 0x1FAFF),
+    (0x20000, 0x2FFFD),
+    (0x30000, 0x3FFFD),
+];
+fn in_table(table: &[(u32, u32)], c: u32) -> bool {
+    table
+        .binary_search_by(|&(lo, hi)| {
+            if hi < c {
+                std::cmp::Ordering::Less
+            } else if lo > c {
+                std::cmp::Ordering::Greater
+            } else {
+                std::cmp::Ordering::Equal
+            }
+        })
+        .is_ok()
+}
+/// Number of terminal columns `ch` occupies: 0, 1 or 2.
+pub fn char_width(ch: char) -> usize {
+    let c = ch as u32;
+    // printable ascii skips the table lookups
+    if (0x20..0x7F).contains(&c) {
+        return 1;
+    }
+    if c < 0x20 || (0x7F..0xA0).contains(&c) || in_table(ZERO_WIDTH, c) {
+        0
+    } else if in_table(WIDE, c) {
+        2
+    } else {
+        1
+    }
+}
+pub fn display_width(s: &str) -> usize {
+    s.chars().map(char_width).sum()
+}
+/// Display width of the widest line, the width a frame needs for multi-line text.
+pub fn max_line_width(s: &str) -> usize {
+    s.lines().map(display_width).max().unwrap_or(0)
+}
+pub fn line_count(s: &str) -> usize {
+    s.lines().count()
+}
+/// Longest prefix of `s` that fits into `w` columns. Never splits a char, and keeps
+/// zero-width marks attached to the last char that fits.
+pub fn truncate_to_width(s: &str, w: usize) -> &str {
+    let mut used = 0;
+    for (i, ch) in s.char_indices() {
+        used += char_width(ch);
+        if used > w {
+            return &s[..i];
+        }
+    }
+    s
+}
+/// Case-insensitive subsequence match of `query` in `candidate`: a score (higher is
+/// better) and the char indices that matched, or `None` if `query` isn't a subsequence.
+/// Consecutive matches and matches at word starts score extra.
+pub fn fuzzy_score(query: &str, candidate: &str) -> Option<(i32, Vec<usize>)> {
+    let chars: Vec<char> = candidate.chars().collect();
+    let mut indices = Vec::new();
+    let mut score = 0;
+    let mut next = 0;
+    for q in query.chars().flat_map(char::to_lowercase) {
+        let i = (next..chars.len()).find(|&i| chars[i].to_lowercase().eq(Some(q)))?;
+        score += 1;
+        if indices.last().is_some_and(|&last| last + 1 == i) {
+            score += 5;
+        }
+        let word_start = i == 0
+            || !chars[i - 1].is_alphanumeric()
+            || (chars[i - 1].is_lowercase() && chars[i].is_uppercase());
+        if word_start {
+            score += 3;
+        }
+        indices.push(i);
+        next = i + 1;
+    }
+    Some((score, indices))
+}
+/// Guide prefix for a tree node. `last[i]` says whether the node's ancestor at depth `i`
+/// (the node itself for the final entry) is the last of its siblings; roots pass `&[]`.
+pub fn tree_prefix(last: &[bool]) -> String {
+    let Some((&is_last, ancestors)) = last.split_last() else {
+        return String::new();
+    };
+    let mut prefix = String::with_capacity(3 * last.len());
+    for &done in ancestors {
+        prefix.push_str(if done { "   " } else { "│  " });
+    }
+    prefix.push_str(if is_last { "└─ " } else { "├─ " });
+    prefix
+}
+#[cfg(test)]
+mod test {
+    use super::*;
+    #[test]
+    fn width_ascii_cjk_emoji() {
+        assert_eq!(display_width("hello"), 5);
+        assert_eq!(display_width("日本語"), 6);
+        assert_eq!(display_width("a😀b"), 4);
+        assert_eq!(display_width("e\u{301}"), 1);
+        assert_eq!(display_width(""), 0);
+    }
+    #[test]
+    fn multiline_measure() {
+        assert_eq!(max_line_width("ab\ncdef\ng"), 4);
+        assert_eq!(line_count("ab\ncdef\ng"), 3);
+        assert_eq!(max_line_width("日本\nabc"), 4);
+        assert_eq!((max_line_width(""), line_count("")), (0, 0));
+    }
+    #[test]
+    fn fuzzy_ranking() {
+        let (bar, bar_idx) = fuzzy_score("fb", "foo_bar").unwrap();
+        let (fizz, _) = fuzzy_score("fb", "fizz_abc").unwrap();
+        assert!(bar > fizz);
+        assert_eq!(bar_idx, [0, 4]);
+        assert!(fuzzy_score("fo", "foo").unwrap().0 > fuzzy_score("fo", "f_o").unwrap().0);
+        assert_eq!(
+            fuzzy_score("FB", "fooBar").map(|(_, i)| i),
+            Some(vec![0, 3])
+        );
+        assert_eq!(fuzzy_score("bf", "foo_bar"), None);
+        assert_eq!(fuzzy_score("", "abc"), Some((0, vec![])));
+    }
+    #[test]
+    fn tree_guides() {
+        assert_eq!(tree_prefix(&[]), "");
+        assert_eq!(tree_prefix(&[false]), "├─ ");
+        assert_eq!(tree_prefix(&[false, true]), "│  └─ ");
+        assert_eq!(tree_prefix(&[true, false]), "   ├─ ");
+    }
+    #[test]
+    fn truncate_on_boundaries() {
+        assert_eq!(truncate_to_width("hello", 3), "hel");
+        assert_eq!(truncate_to_width("hello", 10), "hello");
+        // a wide char that doesn't fit entirely is dropped
+        assert_eq!(truncate_to_width("日本語", 3), "日");
+        assert_eq!(truncate_to_width("日本語", 4), "日本");
+        // the combining accent stays with its base char
+        assert_eq!(truncate_to_width("e\u{301}xyz", 1), "e\u{301}");
+        assert_eq!(truncate_to_width("abc", 0), "");
+    }
+}